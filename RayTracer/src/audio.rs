@@ -65,6 +65,21 @@ impl AudioPlayer {
         }
     }
 
+    // Congela la música y el efecto en curso (menú de pausa)
+    pub fn pause_all(&self) {
+        self.pause_music();
+        if let Ok(sink) = self.sfx_sink.lock() {
+            sink.pause();
+        }
+    }
+
+    pub fn resume_all(&self) {
+        self.play_music();
+        if let Ok(sink) = self.sfx_sink.lock() {
+            sink.play();
+        }
+    }
+
     pub fn stop_music(&self) {
         if let Ok(sink) = self.music_sink.lock() {
             sink.stop();
@@ -2,55 +2,241 @@ use raylib::prelude::*;
 use crate::player::Player;
 use crate::maze::Maze;
 use crate::framebuffer::Framebuffer;
+use crate::door::{Door, DoorAxis, Doors};
+
+// Tamaño (en texeles) de las texturas de pared
+pub const TEXTURE_SIZE: f32 = 128.0;
+
+// Límite de celdas recorridas por rayo, por si el laberinto está abierto
+const MAX_STEPS: usize = 4096;
+
+// Cara de la celda contra la que chocó el rayo
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Side {
+    North,
+    South,
+    East,
+    West,
+}
+
+// Factor de iluminación por cara, para que los pasillos se lean en 3D
+#[derive(Debug, Clone, Copy)]
+pub struct WallShading {
+    pub north: f32,
+    pub south: f32,
+    pub east: f32,
+    pub west: f32,
+}
+
+impl WallShading {
+    pub fn factor(&self, side: Side) -> f32 {
+        match side {
+            Side::North => self.north,
+            Side::South => self.south,
+            Side::East => self.east,
+            Side::West => self.west,
+        }
+    }
+
+    pub fn apply(&self, color: Color, side: Side) -> Color {
+        let f = self.factor(side).clamp(0.0, 1.0);
+        Color::new(
+            (color.r as f32 * f) as u8,
+            (color.g as f32 * f) as u8,
+            (color.b as f32 * f) as u8,
+            color.a,
+        )
+    }
+}
+
+impl Default for WallShading {
+    // Caras este/oeste más oscuras que norte/sur
+    fn default() -> Self {
+        WallShading {
+            north: 1.0,
+            south: 1.0,
+            east: 0.7,
+            west: 0.7,
+        }
+    }
+}
 
 pub struct Intersect {
     pub distance: f32,
     pub impact: char,
     pub tx: usize,
+    pub side: Side,
+    pub hit: Vector2, // Punto exacto de impacto en coordenadas de mundo
+}
+
+impl Intersect {
+    // Columna de textura para la cara impactada. Se invierte en las caras este y
+    // norte para que la textura se vea siempre de izquierda a derecha desde el jugador.
+    pub fn texture_column(&self, block_size: usize, texture_width: usize) -> usize {
+        let block = block_size as f32;
+        let u = match self.side {
+            Side::East | Side::West => (self.hit.y / block).fract(),
+            Side::North | Side::South => (self.hit.x / block).fract(),
+        };
+        let u = match self.side {
+            Side::West | Side::South => u,
+            Side::East | Side::North => 1.0 - u,
+        };
+        ((u * texture_width as f32) as usize).min(texture_width.saturating_sub(1))
+    }
+}
+
+// Celda del laberinto en (x, y), o None si está fuera de la cuadrícula
+fn cell_at(maze: &Maze, x: isize, y: isize) -> Option<char> {
+    if x < 0 || y < 0 {
+        return None;
+    }
+    maze.get(y as usize).and_then(|row| row.get(x as usize)).copied()
 }
 
 pub fn cast_ray(
     framebuffer: &mut Framebuffer,
     maze: &Maze,
+    doors: &Doors,
     player: &Player,
     a: f32,
     block_size: usize,
     draw: bool,
 ) -> Intersect {
-    let mut d = 0.0;
-    framebuffer.set_current_color(Color::WHITE);
-
-    loop {
-        let cos = d * a.cos();
-        let sin = d * a.sin();
-        let x = (player.pos.x + cos) as usize;
-        let y = (player.pos.y + sin) as usize;
-
-        let i = x / block_size;
-        let j = y / block_size;
-        
-        // 'g' debe ser transparente para los rayos (como ' ')
-        if maze[j][i] != ' ' && maze[j][i] != 'g' {
-            let hitx = x - i * block_size;
-            let hity = y - j * block_size;
-            let mut maxhit = hity;
-
-            if 1 < hitx && hitx < block_size - 1 {
-                maxhit = hitx;
-            }
+    let intersect = trace_ray(maze, doors, player, a, block_size);
+
+    if draw {
+        framebuffer.set_current_color(Color::WHITE);
+        framebuffer.draw_line(
+            player.pos.x as i32,
+            player.pos.y as i32,
+            intersect.hit.x as i32,
+            intersect.hit.y as i32,
+        );
+    }
+
+    intersect
+}
 
-            let x = ((maxhit as f32* 128.0) / block_size as f32) as usize;
-            return Intersect {
-                distance: d,
-                impact: maze[j][i],
-                tx: x,
-            }; 
+// Choque del rayo con el panel de una puerta, que está en el plano central de
+// su celda y se desliza hacia un costado según `openness`. `t_enter` y
+// `t_exit` delimitan el tramo del rayo dentro de la celda. Devuelve la
+// distancia, la coordenada de textura (0..1) y la cara impactada.
+fn door_hit(
+    door: &Door,
+    (map_x, map_y): (isize, isize),
+    (pos_x, pos_y): (f32, f32),
+    (dir_x, dir_y): (f32, f32),
+    t_enter: f32,
+    t_exit: f32,
+) -> Option<(f32, f32, Side)> {
+    let (t, along, side) = match door.axis {
+        DoorAxis::Vertical if dir_x != 0.0 => {
+            let t = (map_x as f32 + 0.5 - pos_x) / dir_x;
+            let side = if dir_x > 0.0 { Side::West } else { Side::East };
+            (t, pos_y + dir_y * t - map_y as f32, side)
         }
-        
-        if draw {
-            framebuffer.set_pixel(x as i32, y as i32);
+        DoorAxis::Horizontal if dir_y != 0.0 => {
+            let t = (map_y as f32 + 0.5 - pos_y) / dir_y;
+            let side = if dir_y > 0.0 { Side::North } else { Side::South };
+            (t, pos_x + dir_x * t - map_x as f32, side)
         }
-        
-        d += 1.0;
+        _ => return None,
+    };
+    // El rayo pasa por el hueco que deja la puerta abierta
+    if t < t_enter || t > t_exit || along < door.openness {
+        return None;
     }
-}
\ No newline at end of file
+    Some((t, along - door.openness, side))
+}
+
+// Recorrido DDA: avanza celda por celda a lo largo del rayo en lugar de
+// marchar a pasos fijos, así la distancia de impacto es exacta. No toca el
+// framebuffer, así que se puede llamar desde varios hilos a la vez.
+pub fn trace_ray(maze: &Maze, doors: &Doors, player: &Player, a: f32, block_size: usize) -> Intersect {
+    let block = block_size as f32;
+    let dir_x = a.cos();
+    let dir_y = a.sin();
+
+    // Posición del jugador en unidades de celda
+    let pos_x = player.pos.x / block;
+    let pos_y = player.pos.y / block;
+    let mut map_x = pos_x.floor() as isize;
+    let mut map_y = pos_y.floor() as isize;
+
+    // Distancia (en celdas) que recorre el rayo para cruzar una celda completa en cada eje
+    let delta_x = if dir_x == 0.0 { f32::INFINITY } else { (1.0 / dir_x).abs() };
+    let delta_y = if dir_y == 0.0 { f32::INFINITY } else { (1.0 / dir_y).abs() };
+
+    // Distancia hasta el primer borde vertical/horizontal de la cuadrícula
+    let (step_x, mut side_x) = if dir_x == 0.0 {
+        (0, f32::INFINITY)
+    } else if dir_x < 0.0 {
+        (-1, (pos_x - map_x as f32) * delta_x)
+    } else {
+        (1, (map_x as f32 + 1.0 - pos_x) * delta_x)
+    };
+    let (step_y, mut side_y) = if dir_y == 0.0 {
+        (0, f32::INFINITY)
+    } else if dir_y < 0.0 {
+        (-1, (pos_y - map_y as f32) * delta_y)
+    } else {
+        (1, (map_y as f32 + 1.0 - pos_y) * delta_y)
+    };
+
+    let mut t = 0.0;
+    let mut side = Side::West;
+    // Si el rayo escapa del laberinto se trata como pared genérica
+    let mut impact = '#';
+    // Coordenada de textura cuando el rayo choca con una puerta
+    let mut door_u = None;
+
+    for _ in 0..MAX_STEPS {
+        if side_x < side_y {
+            t = side_x;
+            side_x += delta_x;
+            map_x += step_x;
+            side = if step_x > 0 { Side::West } else { Side::East };
+        } else {
+            t = side_y;
+            side_y += delta_y;
+            map_y += step_y;
+            side = if step_y > 0 { Side::North } else { Side::South };
+        }
+
+        match cell_at(maze, map_x, map_y) {
+            None => break,
+            // 'g' debe ser transparente para los rayos (como ' ')
+            Some(' ') | Some('g') => continue,
+            Some(cell) => {
+                if let Some(door) = doors.get((map_x as usize, map_y as usize)) {
+                    let hit = door_hit(door, (map_x, map_y), (pos_x, pos_y), (dir_x, dir_y), t, side_x.min(side_y));
+                    let Some((t_door, u, door_side)) = hit else {
+                        continue;
+                    };
+                    t = t_door;
+                    side = door_side;
+                    door_u = Some(u);
+                }
+                impact = cell;
+                break;
+            }
+        }
+    }
+
+    // Punto exacto de impacto en coordenadas de mundo
+    let hit = Vector2::new(player.pos.x + dir_x * t * block, player.pos.y + dir_y * t * block);
+
+    let mut intersect = Intersect {
+        distance: t * block,
+        impact,
+        tx: 0,
+        side,
+        hit,
+    };
+    intersect.tx = match door_u {
+        Some(u) => ((u * TEXTURE_SIZE) as usize).min(TEXTURE_SIZE as usize - 1),
+        None => intersect.texture_column(block_size, TEXTURE_SIZE as usize),
+    };
+    intersect
+}
@@ -0,0 +1,156 @@
+// clock.rs
+// Reloj de una partida. Avanza solo con el `dt` que le pasa el bucle
+// principal, así las bonificaciones, penalizaciones, cámara lenta y pausas se
+// suman sobre el mismo tiempo restante en vez de recalcularlo con el reloj real.
+
+pub struct GameClock {
+    limit: f32,     // Tiempo máximo del nivel (tope de las bonificaciones)
+    remaining: f32,
+    elapsed: f32,   // Tiempo de juego transcurrido (con cámara lenta, sin pausas)
+    time_scale: f32,
+    slow_motion_left: f32, // Segundos reales que quedan de cámara lenta
+    paused: bool,
+}
+
+impl GameClock {
+    pub fn new(limit: f32) -> Self {
+        GameClock {
+            limit,
+            remaining: limit,
+            elapsed: 0.0,
+            time_scale: 1.0,
+            slow_motion_left: 0.0,
+            paused: false,
+        }
+    }
+
+    // Avanza `dt` segundos reales y devuelve el tiempo de juego que pasó
+    // (0 en pausa, menos en cámara lenta); es el `dt` para mover el mundo.
+    pub fn tick(&mut self, dt: f32) -> f32 {
+        if self.paused || dt <= 0.0 {
+            return 0.0;
+        }
+        let game_dt = dt * self.time_scale;
+        if self.slow_motion_left > 0.0 {
+            self.slow_motion_left -= dt;
+            if self.slow_motion_left <= 0.0 {
+                self.slow_motion_left = 0.0;
+                self.time_scale = 1.0;
+            }
+        }
+        self.elapsed += game_dt;
+        self.remaining = (self.remaining - game_dt).max(0.0);
+        game_dt
+    }
+
+    // Suma tiempo (p. ej. una batería), sin pasar del máximo del nivel
+    pub fn add_bonus(&mut self, seconds: f32) {
+        self.remaining = (self.remaining + seconds.max(0.0)).min(self.limit);
+    }
+
+    pub fn apply_penalty(&mut self, seconds: f32) {
+        self.remaining = (self.remaining - seconds.max(0.0)).max(0.0);
+    }
+
+    // El juego corre a `scale` de la velocidad normal durante `real_seconds`
+    pub fn start_slow_motion(&mut self, scale: f32, real_seconds: f32) {
+        self.time_scale = scale.clamp(0.0, 1.0);
+        self.slow_motion_left = real_seconds.max(0.0);
+    }
+
+    pub fn set_paused(&mut self, paused: bool) {
+        self.paused = paused;
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    pub fn remaining(&self) -> f32 {
+        self.remaining
+    }
+
+    pub fn elapsed(&self) -> f32 {
+        self.elapsed
+    }
+
+    pub fn limit(&self) -> f32 {
+        self.limit
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.remaining <= 0.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ticks_drain_remaining_time() {
+        let mut clock = GameClock::new(60.0);
+        for _ in 0..60 {
+            clock.tick(0.5);
+        }
+        assert_eq!(clock.remaining(), 30.0);
+        assert_eq!(clock.elapsed(), 30.0);
+    }
+
+    #[test]
+    fn bonus_survives_following_ticks() {
+        let mut clock = GameClock::new(60.0);
+        clock.tick(40.0);
+        clock.add_bonus(30.0);
+        clock.tick(1.0);
+        assert_eq!(clock.remaining(), 49.0);
+    }
+
+    #[test]
+    fn bonus_is_capped_at_limit() {
+        let mut clock = GameClock::new(60.0);
+        clock.tick(10.0);
+        clock.add_bonus(30.0);
+        assert_eq!(clock.remaining(), 60.0);
+    }
+
+    #[test]
+    fn penalty_can_expire_the_clock() {
+        let mut clock = GameClock::new(60.0);
+        clock.apply_penalty(15.0);
+        assert_eq!(clock.remaining(), 45.0);
+        clock.apply_penalty(100.0);
+        assert!(clock.is_expired());
+    }
+
+    #[test]
+    fn pause_freezes_time() {
+        let mut clock = GameClock::new(60.0);
+        clock.set_paused(true);
+        assert_eq!(clock.tick(5.0), 0.0);
+        assert_eq!(clock.remaining(), 60.0);
+        clock.set_paused(false);
+        clock.tick(5.0);
+        assert_eq!(clock.remaining(), 55.0);
+    }
+
+    #[test]
+    fn slow_motion_scales_game_time_then_ends() {
+        let mut clock = GameClock::new(60.0);
+        clock.start_slow_motion(0.5, 2.0);
+        assert_eq!(clock.tick(1.0), 0.5);
+        assert_eq!(clock.tick(1.0), 0.5);
+        assert_eq!(clock.tick(1.0), 1.0);
+        assert_eq!(clock.remaining(), 58.0);
+    }
+
+    #[test]
+    fn slow_motion_does_not_run_out_while_paused() {
+        let mut clock = GameClock::new(60.0);
+        clock.start_slow_motion(0.5, 1.0);
+        clock.set_paused(true);
+        clock.tick(10.0);
+        clock.set_paused(false);
+        assert_eq!(clock.tick(1.0), 0.5);
+    }
+}
@@ -0,0 +1,128 @@
+// door.rs
+// Estado de las puertas corredizas del nivel. La cuadrícula solo guarda el
+// glifo ('D' o la puerta de una llave, ver `KeyColor::gate_glyph`); cuánto
+// está abierta cada puerta vive aquí y lo usan el raycaster y las colisiones.
+use raylib::prelude::*;
+use std::collections::HashMap;
+use crate::inventory::Inventory;
+use crate::key::KeyColor;
+use crate::maze::{is_door, is_walkable, Maze};
+use crate::navigation::Cell;
+
+// Fracción de la puerta que se desliza por segundo
+const DOOR_SPEED: f32 = 1.5;
+// Distancia (en celdas, desde el centro de la puerta) a la que se abre sola
+const OPEN_RANGE: f32 = 1.5;
+// A partir de esta apertura el jugador puede pasar
+const PASSABLE_OPENNESS: f32 = 0.8;
+
+// Orientación del panel, que ocupa el plano central de la celda
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DoorAxis {
+    Vertical,   // Plano x = centro: se cruza yendo de este a oeste
+    Horizontal, // Plano y = centro: se cruza yendo de norte a sur
+}
+
+pub struct Door {
+    pub axis: DoorAxis,
+    pub lock: Option<KeyColor>, // Llave necesaria para abrirla
+    pub openness: f32,          // 0 = cerrada, 1 = abierta del todo
+}
+
+#[derive(Default)]
+pub struct Doors {
+    doors: HashMap<Cell, Door>,
+}
+
+impl Doors {
+    // Todas las puertas del laberinto, cerradas
+    pub fn from_maze(maze: &Maze) -> Self {
+        // Las paredes y las demás puertas forman la línea del marco
+        let frame_at = |x: Option<usize>, y: Option<usize>| match (x, y) {
+            (Some(x), Some(y)) => maze
+                .get(y)
+                .and_then(|row| row.get(x))
+                .is_none_or(|&cell| !is_walkable(cell) || is_door(cell)),
+            _ => true,
+        };
+        let mut doors = HashMap::new();
+        for (y, row) in maze.iter().enumerate() {
+            for (x, &cell) in row.iter().enumerate() {
+                if !is_door(cell) {
+                    continue;
+                }
+                let frame_west_east = frame_at(x.checked_sub(1), Some(y)) && frame_at(Some(x + 1), Some(y));
+                let frame_north_south = frame_at(Some(x), y.checked_sub(1)) && frame_at(Some(x), Some(y + 1));
+                // Marco al norte y sur: el pasillo corre de este a oeste (panel vertical).
+                // Marco al este y oeste: corre de norte a sur (panel horizontal).
+                let axis = if frame_west_east && !frame_north_south {
+                    DoorAxis::Horizontal
+                } else {
+                    DoorAxis::Vertical
+                };
+                doors.insert((x, y), Door {
+                    axis,
+                    lock: KeyColor::from_gate_glyph(cell),
+                    openness: 0.0,
+                });
+            }
+        }
+        Doors { doors }
+    }
+
+    pub fn get(&self, cell: Cell) -> Option<&Door> {
+        self.doors.get(&cell)
+    }
+
+    // Las celdas sin puerta siempre son pasables (las paredes se revisan aparte)
+    pub fn is_passable(&self, cell: Cell) -> bool {
+        self.doors.get(&cell).is_none_or(|door| door.openness >= PASSABLE_OPENNESS)
+    }
+
+    // Abre las puertas cercanas al jugador (si tiene la llave) y cierra las demás
+    pub fn update(&mut self, player_pos: Vector2, block_size: usize, inventory: &Inventory, dt: f32) {
+        let block = block_size as f32;
+        let player_cell = ((player_pos.x / block) as usize, (player_pos.y / block) as usize);
+        for (&(x, y), door) in self.doors.iter_mut() {
+            let center = Vector2::new((x as f32 + 0.5) * block, (y as f32 + 0.5) * block);
+            let distance = ((player_pos.x - center.x).powi(2) + (player_pos.y - center.y).powi(2)).sqrt() / block;
+            let unlocked = door.lock.is_none_or(|color| inventory.has_key(color));
+            // Nunca se cierra con el jugador adentro
+            let open = player_cell == (x, y) || (distance <= OPEN_RANGE && unlocked);
+            let target = if open { 1.0 } else { 0.0 };
+            let step = DOOR_SPEED * dt;
+            door.openness = if door.openness < target {
+                (door.openness + step).min(target)
+            } else {
+                (door.openness - step).max(target)
+            };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grid(rows: &[&str]) -> Maze {
+        rows.iter().map(|row| row.chars().collect()).collect()
+    }
+
+    #[test]
+    fn axis_follows_the_frame_around_the_door() {
+        let maze = grid(&[
+            "+--+--+",
+            "|  D  |",
+            "+--+ R+",
+            "|     |",
+            "+-RR--+",
+            "|     |",
+            "+--+--+",
+        ]);
+        let doors = Doors::from_maze(&maze);
+        assert_eq!(doors.get((3, 1)).unwrap().axis, DoorAxis::Vertical);
+        // Con una puerta al lado, esta cuenta como parte del marco
+        assert_eq!(doors.get((2, 4)).unwrap().axis, DoorAxis::Horizontal);
+        assert_eq!(doors.get((3, 4)).unwrap().axis, DoorAxis::Horizontal);
+    }
+}
@@ -1,44 +1,172 @@
 use raylib::prelude::*;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::marker::PhantomData;
+use std::ops::Range;
 
+// Ancho (o alto) mínimo de una banda de render en paralelo
+const MIN_BAND_SIZE: usize = 64;
+
+// Cómo se escala el cuadro cuando la ventana es más grande que el framebuffer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScaleMode {
+    Integer, // Factor entero con filtrado por punto (píxeles nítidos)
+    Linear,  // Ajuste libre con filtrado bilineal
+}
+
+// Búfer de color en memoria (RGBA, fila por fila). Todo el dibujo se hace en
+// CPU sin llamadas a raylib; solo `swap_buffers` sube el cuadro a la GPU.
 pub struct Framebuffer {
     pub width: i32,
     pub height: i32,
-    pub color_buffer: Image,
+    pixels: Vec<[u8; 4]>,
     background_color: Color,
     current_color: Color,
+    scale_mode: ScaleMode,
+    texture: Option<Texture2D>, // Textura persistente, se actualiza en cada cuadro
+}
+
+#[inline]
+fn to_rgba(color: Color) -> [u8; 4] {
+    [color.r, color.g, color.b, color.a]
 }
 
 impl Framebuffer {
     pub fn new(width: i32, height: i32, background_color: Color) -> Self {
-        let color_buffer = Image::gen_image_color(width, height, background_color);
+        let pixels = vec![to_rgba(background_color); (width.max(0) * height.max(0)) as usize];
         Framebuffer {
             width,
             height,
-            color_buffer,
+            pixels,
             background_color,
             current_color: Color::WHITE,
+            scale_mode: ScaleMode::Integer,
+            texture: None,
+        }
+    }
+
+    #[inline]
+    fn index(&self, x: i32, y: i32) -> Option<usize> {
+        if x >= 0 && x < self.width && y >= 0 && y < self.height {
+            Some((y * self.width + x) as usize)
+        } else {
+            None
         }
     }
 
     pub fn clear(&mut self) {
-        self.color_buffer.clear_background(self.background_color);
+        let color = to_rgba(self.background_color);
+        self.pixels.fill(color);
     }
 
+    #[inline]
     pub fn set_pixel(&mut self, x: i32, y: i32) {
-        if x >= 0 && x < self.width && y >= 0 && y < self.height {
-            self.color_buffer.draw_pixel(x, y, self.current_color);
+        if let Some(i) = self.index(x, y) {
+            self.pixels[i] = to_rgba(self.current_color);
         }
     }
-    
-    // --- CORRECCIÓN ---
-    // Cambiamos &self por &mut self para cumplir con los requisitos del "borrow checker"
-    // y la firma de la función `get_color` de raylib.
-    pub fn get_pixel_color(&mut self, x: i32, y: i32) -> Option<Color> {
-        if x >= 0 && x < self.width && y >= 0 && y < self.height {
-            Some(self.color_buffer.get_color(x, y))
-        } else {
-            None
+
+    // Como set_pixel, pero sin tocar el color actual
+    #[inline]
+    pub fn set_pixel_color(&mut self, x: i32, y: i32, color: Color) {
+        if let Some(i) = self.index(x, y) {
+            self.pixels[i] = to_rgba(color);
+        }
+    }
+
+    #[inline]
+    pub fn get_pixel_color(&self, x: i32, y: i32) -> Option<Color> {
+        self.index(x, y).map(|i| {
+            let [r, g, b, a] = self.pixels[i];
+            Color::new(r, g, b, a)
+        })
+    }
+
+    // Rellena [x0, x1) de la fila y con el color actual (recortado a la pantalla)
+    pub fn fill_row(&mut self, y: i32, x0: i32, x1: i32) {
+        if y < 0 || y >= self.height {
+            return;
+        }
+        let x0 = x0.clamp(0, self.width);
+        let x1 = x1.clamp(0, self.width);
+        if x0 >= x1 {
+            return;
         }
+        let row = (y * self.width) as usize;
+        let color = to_rgba(self.current_color);
+        self.pixels[row + x0 as usize..row + x1 as usize].fill(color);
+    }
+
+    pub fn fill_rect(&mut self, x: i32, y: i32, width: i32, height: i32) {
+        for row in y.max(0)..(y + height).min(self.height) {
+            self.fill_row(row, x, x + width);
+        }
+    }
+
+    // Copia `src` escalándolo (vecino más cercano) al tamaño completo de este framebuffer
+    pub fn blit_scaled(&mut self, src: &Framebuffer) {
+        if src.width == self.width && src.height == self.height {
+            self.pixels.copy_from_slice(&src.pixels);
+            return;
+        }
+        if src.width <= 0 || src.height <= 0 {
+            return;
+        }
+        let dst_width = self.width as usize;
+        let columns: Vec<usize> = (0..dst_width)
+            .map(|x| x * src.width as usize / dst_width)
+            .collect();
+        for (y, row) in self.pixels.chunks_mut(dst_width).enumerate() {
+            let sy = y * src.height as usize / self.height as usize;
+            let src_row = &src.pixels[sy * src.width as usize..(sy + 1) * src.width as usize];
+            for (pixel, &sx) in row.iter_mut().zip(&columns) {
+                *pixel = src_row[sx];
+            }
+        }
+    }
+
+    // Divide el framebuffer en `count` bandas de columnas contiguas que no se
+    // solapan, para que cada hilo dibuje las suyas en paralelo. Nunca hay bandas
+    // de menos de MIN_BAND_SIZE columnas: no compensaría lanzar un hilo para ellas.
+    pub fn column_bands(&mut self, count: usize) -> Vec<ColumnBand<'_>> {
+        let width = self.width.max(0) as usize;
+        let count = count.clamp(1, (width / MIN_BAND_SIZE).max(1));
+        let ptr = self.pixels.as_mut_ptr();
+        (0..count)
+            .map(|i| ColumnBand {
+                ptr,
+                stride: width,
+                height: self.height,
+                columns: (i * width / count) as i32..((i + 1) * width / count) as i32,
+                _marker: PhantomData,
+            })
+            .collect()
+    }
+
+    // Divide el framebuffer en `count` bandas de filas contiguas que no se solapan
+    pub fn row_bands(&mut self, count: usize) -> Vec<RowBand<'_>> {
+        let width = self.width.max(0) as usize;
+        let height = self.height.max(0) as usize;
+        let count = count.clamp(1, (height / MIN_BAND_SIZE).max(1));
+        let mut bands = Vec::with_capacity(count);
+        let mut rest = self.pixels.as_mut_slice();
+        for i in 0..count {
+            let (start, end) = (i * height / count, (i + 1) * height / count);
+            let (band, tail) = rest.split_at_mut((end - start) * width);
+            rest = tail;
+            bands.push(RowBand {
+                rows: start as i32..end as i32,
+                width: self.width,
+                pixels: band,
+            });
+        }
+        bands
+    }
+
+    // Píxeles en bruto (RGBA, fila por fila), para comparar cuadros en las pruebas
+    #[cfg(test)]
+    pub fn pixels(&self) -> &[[u8; 4]] {
+        &self.pixels
     }
 
     pub fn set_background_color(&mut self, color: Color) {
@@ -49,12 +177,95 @@ impl Framebuffer {
         self.current_color = color;
     }
 
-    pub fn swap_buffers(&self, window: &mut RaylibHandle, raylib_thread: &RaylibThread) {
-        if let Ok(texture) = window.load_texture_from_image(raylib_thread, &self.color_buffer) {
-            let mut renderer = window.begin_drawing(raylib_thread);
-            renderer.clear_background(self.background_color);
-            renderer.draw_texture(&texture, 0, 0, Color::WHITE);
+    pub fn set_scale_mode(&mut self, mode: ScaleMode) {
+        if self.scale_mode != mode {
+            self.scale_mode = mode;
+            self.texture = None; // Se recrea con el nuevo filtro en el próximo cuadro
+        }
+    }
+
+    // Sube el cuadro a la textura persistente (creándola la primera vez) y la
+    // presenta centrada y escalada al tamaño actual de la ventana.
+    pub fn swap_buffers(&mut self, window: &mut RaylibHandle, raylib_thread: &RaylibThread) {
+        let bytes = self.pixels.as_flattened();
+        match self.texture.as_mut() {
+            Some(texture) => {
+                if let Err(e) = texture.update_texture(bytes) {
+                    eprintln!("Error al actualizar la textura del framebuffer: {}", e);
+                }
+            }
+            None => {
+                let image = Image::gen_image_color(self.width, self.height, self.background_color);
+                let data = unsafe { std::slice::from_raw_parts_mut(image.data as *mut u8, bytes.len()) };
+                data.copy_from_slice(bytes);
+                match window.load_texture_from_image(raylib_thread, &image) {
+                    Ok(texture) => {
+                        let filter = match self.scale_mode {
+                            ScaleMode::Integer => TextureFilter::TEXTURE_FILTER_POINT,
+                            ScaleMode::Linear => TextureFilter::TEXTURE_FILTER_BILINEAR,
+                        };
+                        texture.set_texture_filter(raylib_thread, filter);
+                        self.texture = Some(texture);
+                    }
+                    Err(e) => eprintln!("Error al crear la textura del framebuffer: {}", e),
+                }
+            }
+        }
+
+        let window_width = window.get_screen_width() as f32;
+        let window_height = window.get_screen_height() as f32;
+        let fit = (window_width / self.width as f32).min(window_height / self.height as f32);
+        let scale = match self.scale_mode {
+            ScaleMode::Integer => fit.floor().max(1.0),
+            ScaleMode::Linear => fit,
+        };
+        let dest_width = self.width as f32 * scale;
+        let dest_height = self.height as f32 * scale;
+
+        let mut renderer = window.begin_drawing(raylib_thread);
+        renderer.clear_background(self.background_color);
+        if let Some(texture) = &self.texture {
+            renderer.draw_texture_pro(
+                texture,
+                Rectangle::new(0.0, 0.0, self.width as f32, self.height as f32),
+                Rectangle::new(
+                    ((window_width - dest_width) / 2.0).floor(),
+                    ((window_height - dest_height) / 2.0).floor(),
+                    dest_width,
+                    dest_height,
+                ),
+                Vector2::zero(),
+                0.0,
+                Color::WHITE,
+            );
+        }
+    }
+
+    // Guarda el cuadro como PNG (RGBA de 8 bits, deflate sin compresión)
+    pub fn save_png(&self, path: &str) -> io::Result<()> {
+        let mut out = BufWriter::new(File::create(path)?);
+        self.write_png(&mut out)?;
+        out.flush()
+    }
+
+    pub fn write_png(&self, out: &mut impl Write) -> io::Result<()> {
+        let mut raw = Vec::with_capacity(self.pixels.len() * 4 + self.height as usize);
+        for row in self.pixels.chunks(self.width.max(1) as usize) {
+            raw.push(0); // Filtro "None"
+            for pixel in row {
+                raw.extend_from_slice(pixel);
+            }
         }
+
+        let mut ihdr = Vec::with_capacity(13);
+        ihdr.extend_from_slice(&(self.width as u32).to_be_bytes());
+        ihdr.extend_from_slice(&(self.height as u32).to_be_bytes());
+        ihdr.extend_from_slice(&[8, 6, 0, 0, 0]); // 8 bits, RGBA, sin entrelazado
+
+        out.write_all(&PNG_SIGNATURE)?;
+        write_png_chunk(out, b"IHDR", &ihdr)?;
+        write_png_chunk(out, b"IDAT", &zlib_stored(&raw))?;
+        write_png_chunk(out, b"IEND", &[])
     }
 
     pub fn draw_line(&mut self, x1: i32, y1: i32, x2: i32, y2: i32) {
@@ -66,14 +277,14 @@ impl Framebuffer {
         let mut err = dx - dy;
         let mut x = x1;
         let mut y = y1;
-        
+
         loop {
             self.set_pixel(x, y);
-            
+
             if x == x2 && y == y2 {
                 break;
             }
-            
+
             let e2 = 2 * err;
             if e2 > -dy {
                 err -= dy;
@@ -86,3 +297,193 @@ impl Framebuffer {
         }
     }
 }
+
+// Vista mutable de las columnas `columns` de un framebuffer. Solo escribe
+// dentro de su rango de columnas, y las bandas de `column_bands` no se
+// solapan, así que enviarlas a hilos distintos no produce carreras de datos.
+pub struct ColumnBand<'a> {
+    ptr: *mut [u8; 4],
+    stride: usize,
+    height: i32,
+    columns: Range<i32>,
+    _marker: PhantomData<&'a mut [[u8; 4]]>,
+}
+
+unsafe impl Send for ColumnBand<'_> {}
+
+impl ColumnBand<'_> {
+    pub fn columns(&self) -> Range<i32> {
+        self.columns.clone()
+    }
+
+    pub fn height(&self) -> i32 {
+        self.height
+    }
+
+    #[inline]
+    pub fn set_pixel(&mut self, x: i32, y: i32, color: Color) {
+        if self.columns.contains(&x) && y >= 0 && y < self.height {
+            // Seguro: (x, y) está dentro del framebuffer y en la banda propia
+            unsafe {
+                *self.ptr.add(y as usize * self.stride + x as usize) = to_rgba(color);
+            }
+        }
+    }
+}
+
+// Vista mutable de las filas `rows` de un framebuffer (coordenadas globales)
+pub struct RowBand<'a> {
+    rows: Range<i32>,
+    width: i32,
+    pixels: &'a mut [[u8; 4]],
+}
+
+impl RowBand<'_> {
+    pub fn rows(&self) -> Range<i32> {
+        self.rows.clone()
+    }
+
+    #[inline]
+    fn index(&self, x: i32, y: i32) -> Option<usize> {
+        if x >= 0 && x < self.width && self.rows.contains(&y) {
+            Some(((y - self.rows.start) * self.width + x) as usize)
+        } else {
+            None
+        }
+    }
+
+    #[inline]
+    pub fn get_pixel_color(&self, x: i32, y: i32) -> Option<Color> {
+        self.index(x, y).map(|i| {
+            let [r, g, b, a] = self.pixels[i];
+            Color::new(r, g, b, a)
+        })
+    }
+
+    #[inline]
+    pub fn set_pixel(&mut self, x: i32, y: i32, color: Color) {
+        if let Some(i) = self.index(x, y) {
+            self.pixels[i] = to_rgba(color);
+        }
+    }
+}
+
+const PNG_SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'];
+
+fn write_png_chunk(out: &mut impl Write, kind: &[u8; 4], data: &[u8]) -> io::Result<()> {
+    out.write_all(&(data.len() as u32).to_be_bytes())?;
+    out.write_all(kind)?;
+    out.write_all(data)?;
+    let crc = crc32(&[kind.as_slice(), data].concat());
+    out.write_all(&crc.to_be_bytes())
+}
+
+// Flujo zlib con bloques deflate "stored": sin compresión, pero válido para cualquier lector PNG
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(data.len() + data.len() / 65535 * 5 + 11);
+    out.extend_from_slice(&[0x78, 0x01]);
+    let mut blocks = data.chunks(65535).peekable();
+    if blocks.peek().is_none() {
+        out.extend_from_slice(&[1, 0, 0, 0xff, 0xff]);
+    }
+    while let Some(block) = blocks.next() {
+        let last = blocks.peek().is_none();
+        let len = block.len() as u16;
+        out.push(last as u8);
+        out.extend_from_slice(&len.to_le_bytes());
+        out.extend_from_slice(&(!len).to_le_bytes());
+        out.extend_from_slice(block);
+    }
+    out.extend_from_slice(&adler32(data).to_be_bytes());
+    out
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = 0xffff_ffffu32;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 { (crc >> 1) ^ 0xedb8_8320 } else { crc >> 1 };
+        }
+    }
+    !crc
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for &byte in data {
+        a = (a + byte as u32) % 65521;
+        b = (b + a) % 65521;
+    }
+    (b << 16) | a
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Valores de referencia de zlib (`crc32`/`adler32` de Python)
+    #[test]
+    fn checksums_match_reference_values() {
+        assert_eq!(crc32(b""), 0);
+        assert_eq!(crc32(b"123456789"), 0xcbf4_3926);
+        assert_eq!(crc32(b"IEND"), 0xae42_6082);
+        assert_eq!(adler32(b""), 1);
+        assert_eq!(adler32(b"Wikipedia"), 0x11e6_0398);
+        assert_eq!(adler32(&[0xff; 70_000]), 0x2a28_6e81);
+    }
+
+    // Lee el PNG de vuelta: comprueba los CRC de cada bloque, desarma los
+    // bloques deflate "stored" y compara los píxeles con los originales
+    #[test]
+    fn png_round_trip_keeps_pixels() {
+        let mut framebuffer = Framebuffer::new(200, 180, Color::new(10, 20, 30, 255));
+        framebuffer.set_current_color(Color::new(250, 128, 0, 200));
+        framebuffer.fill_rect(15, 40, 120, 90);
+        framebuffer.set_current_color(Color::SKYBLUE);
+        framebuffer.draw_line(0, 0, 199, 179);
+
+        let mut png = Vec::new();
+        framebuffer.write_png(&mut png).unwrap();
+        assert_eq!(png[..8], PNG_SIGNATURE);
+
+        let mut rest = &png[8..];
+        let mut chunks = Vec::new();
+        while !rest.is_empty() {
+            let len = u32::from_be_bytes(rest[..4].try_into().unwrap()) as usize;
+            let (kind, data) = (&rest[4..8], &rest[8..8 + len]);
+            let crc = u32::from_be_bytes(rest[8 + len..12 + len].try_into().unwrap());
+            assert_eq!(crc, crc32(&rest[4..8 + len]));
+            chunks.push((kind.to_vec(), data.to_vec()));
+            rest = &rest[12 + len..];
+        }
+        let kinds: Vec<&[u8]> = chunks.iter().map(|(kind, _)| kind.as_slice()).collect();
+        assert_eq!(kinds, [b"IHDR".as_slice(), b"IDAT", b"IEND"]);
+        assert_eq!(chunks[0].1[..8], [0, 0, 0, 200, 0, 0, 0, 180]);
+
+        let zlib = &chunks[1].1;
+        let mut deflate = &zlib[2..zlib.len() - 4];
+        let mut raw = Vec::new();
+        loop {
+            let last = deflate[0] & 1 == 1;
+            let len = u16::from_le_bytes([deflate[1], deflate[2]]) as usize;
+            assert_eq!(!u16::from_le_bytes([deflate[3], deflate[4]]) as usize, len);
+            raw.extend_from_slice(&deflate[5..5 + len]);
+            deflate = &deflate[5 + len..];
+            if last {
+                break;
+            }
+        }
+        assert!(deflate.is_empty());
+        assert_eq!(zlib[zlib.len() - 4..], adler32(&raw).to_be_bytes());
+
+        let pixels: Vec<[u8; 4]> = raw
+            .chunks(1 + 200 * 4)
+            .flat_map(|row| {
+                assert_eq!(row[0], 0);
+                row[1..].chunks(4).map(|p| [p[0], p[1], p[2], p[3]])
+            })
+            .collect();
+        assert!(pixels == framebuffer.pixels());
+    }
+}
@@ -0,0 +1,414 @@
+// generator.rs
+// Generación procedural de laberintos con el mismo estilo de glifos que los
+// niveles hechos a mano (`+--+` y `|`). El resultado es texto de nivel que se
+// carga con `maze::parse_level` o se exporta a un archivo:
+//
+//   RayTracer --generate levels/random.txt --algorithm wilson --seed 7 --cells 8x6 --loops 0.1
+use rand::prelude::*;
+use rand::rngs::StdRng;
+use std::collections::VecDeque;
+use crate::arg_value;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Algorithm {
+    Backtracker,
+    Prim,
+    Wilson,
+}
+
+impl Algorithm {
+    pub const ALL: [Algorithm; 3] = [Algorithm::Backtracker, Algorithm::Prim, Algorithm::Wilson];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Algorithm::Backtracker => "backtracker",
+            Algorithm::Prim => "prim",
+            Algorithm::Wilson => "wilson",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Algorithm::ALL.into_iter().find(|a| a.name() == name.trim().to_lowercase())
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct GeneratorOptions {
+    pub algorithm: Algorithm,
+    pub width: usize,  // Celdas transitables en horizontal
+    pub height: usize, // Celdas transitables en vertical
+    pub seed: u64,
+    pub loop_factor: f32, // 0 = laberinto perfecto, 1 = se quitan todas las paredes internas sobrantes
+}
+
+impl Default for GeneratorOptions {
+    fn default() -> Self {
+        GeneratorOptions {
+            algorithm: Algorithm::Backtracker,
+            width: 8,
+            height: 6,
+            seed: 0,
+            loop_factor: 0.1,
+        }
+    }
+}
+
+impl GeneratorOptions {
+    pub fn usage() -> &'static str {
+        "uso: RayTracer --generate <salida.txt> [--algorithm backtracker|prim|wilson] \
+         [--seed N] [--cells 8x6] [--loops 0.0-1.0]"
+    }
+
+    pub fn from_args(args: &[String]) -> Result<Self, String> {
+        let mut options = GeneratorOptions::default();
+        if let Some(value) = arg_value(args, "--algorithm") {
+            options.algorithm = Algorithm::from_name(value)
+                .ok_or_else(|| format!("algoritmo desconocido: {}", value))?;
+        }
+        if let Some(value) = arg_value(args, "--seed") {
+            options.seed = value.parse().map_err(|_| format!("semilla inválida: {}", value))?;
+        }
+        if let Some(value) = arg_value(args, "--cells") {
+            let (w, h) = value
+                .split_once('x')
+                .and_then(|(w, h)| Some((w.parse().ok()?, h.parse().ok()?)))
+                .ok_or_else(|| format!("tamaño inválido: {}", value))?;
+            options.width = w;
+            options.height = h;
+        }
+        if let Some(value) = arg_value(args, "--loops") {
+            options.loop_factor = value.parse().map_err(|_| format!("factor de ciclos inválido: {}", value))?;
+        }
+        if options.width < 2 || options.height < 2 {
+            return Err(String::from("el laberinto necesita al menos 2x2 celdas"));
+        }
+        Ok(options)
+    }
+}
+
+// Paredes entre celdas vecinas. `east[y][x]` separa (x, y) de (x + 1, y) y
+// `south[y][x]` separa (x, y) de (x, y + 1).
+struct Walls {
+    width: usize,
+    height: usize,
+    east: Vec<Vec<bool>>,
+    south: Vec<Vec<bool>>,
+}
+
+impl Walls {
+    fn closed(width: usize, height: usize) -> Self {
+        Walls {
+            width,
+            height,
+            east: vec![vec![true; width]; height],
+            south: vec![vec![true; width]; height],
+        }
+    }
+
+    fn neighbors(&self, (x, y): (usize, usize)) -> Vec<(usize, usize)> {
+        let mut cells = Vec::with_capacity(4);
+        if x > 0 {
+            cells.push((x - 1, y));
+        }
+        if x + 1 < self.width {
+            cells.push((x + 1, y));
+        }
+        if y > 0 {
+            cells.push((x, y - 1));
+        }
+        if y + 1 < self.height {
+            cells.push((x, y + 1));
+        }
+        cells
+    }
+
+    fn carve(&mut self, a: (usize, usize), b: (usize, usize)) {
+        let (is_east, x, y) = between(a, b);
+        if is_east {
+            self.east[y][x] = false;
+        } else {
+            self.south[y][x] = false;
+        }
+    }
+
+    fn is_open(&self, a: (usize, usize), b: (usize, usize)) -> bool {
+        let (is_east, x, y) = between(a, b);
+        if is_east { !self.east[y][x] } else { !self.south[y][x] }
+    }
+}
+
+// Pared entre dos celdas vecinas: (es pared este, x, y) de la celda de la
+// izquierda o de arriba
+fn between(a: (usize, usize), b: (usize, usize)) -> (bool, usize, usize) {
+    (a.1 == b.1, a.0.min(b.0), a.1.min(b.1))
+}
+
+// Backtracker recursivo (DFS con pila): pasillos largos y pocas bifurcaciones
+fn backtracker(walls: &mut Walls, rng: &mut StdRng) {
+    let mut visited = vec![vec![false; walls.width]; walls.height];
+    let mut stack = vec![(0, 0)];
+    visited[0][0] = true;
+    while let Some(&cell) = stack.last() {
+        let unvisited: Vec<_> = walls.neighbors(cell).into_iter().filter(|&(x, y)| !visited[y][x]).collect();
+        match unvisited.choose(rng) {
+            Some(&next) => {
+                walls.carve(cell, next);
+                visited[next.1][next.0] = true;
+                stack.push(next);
+            }
+            None => {
+                stack.pop();
+            }
+        }
+    }
+}
+
+// Prim aleatorio: crece desde una celda tomando paredes de la frontera al azar;
+// muchas bifurcaciones cortas
+fn prim(walls: &mut Walls, rng: &mut StdRng) {
+    let mut in_maze = vec![vec![false; walls.width]; walls.height];
+    in_maze[0][0] = true;
+    let mut frontier: Vec<((usize, usize), (usize, usize))> =
+        walls.neighbors((0, 0)).into_iter().map(|n| ((0, 0), n)).collect();
+    while !frontier.is_empty() {
+        let (from, to) = frontier.swap_remove(rng.gen_range(0..frontier.len()));
+        if in_maze[to.1][to.0] {
+            continue;
+        }
+        walls.carve(from, to);
+        in_maze[to.1][to.0] = true;
+        for n in walls.neighbors(to) {
+            if !in_maze[n.1][n.0] {
+                frontier.push((to, n));
+            }
+        }
+    }
+}
+
+// Wilson: caminatas aleatorias con borrado de ciclos; genera un árbol
+// uniforme, sin sesgo hacia ningún tipo de pasillo
+fn wilson(walls: &mut Walls, rng: &mut StdRng) {
+    let (width, height) = (walls.width, walls.height);
+    let mut in_maze = vec![vec![false; width]; height];
+    in_maze[0][0] = true;
+    // Dirección de salida de cada celda en la caminata actual
+    let mut next: Vec<Vec<Option<(usize, usize)>>> = vec![vec![None; width]; height];
+
+    for start_y in 0..height {
+        for start_x in 0..width {
+            if in_maze[start_y][start_x] {
+                continue;
+            }
+            // Caminar hasta tocar el laberinto; sobrescribir la salida borra los ciclos
+            let mut cell = (start_x, start_y);
+            while !in_maze[cell.1][cell.0] {
+                let step = *walls.neighbors(cell).choose(rng).expect("cada celda tiene vecinos");
+                next[cell.1][cell.0] = Some(step);
+                cell = step;
+            }
+            // Agregar el camino sin ciclos al laberinto
+            let mut cell = (start_x, start_y);
+            while !in_maze[cell.1][cell.0] {
+                let step = next[cell.1][cell.0].expect("la caminata pasó por esta celda");
+                walls.carve(cell, step);
+                in_maze[cell.1][cell.0] = true;
+                cell = step;
+            }
+        }
+    }
+}
+
+// Quita una fracción de las paredes internas restantes para crear ciclos
+fn add_loops(walls: &mut Walls, loop_factor: f32, rng: &mut StdRng) {
+    let mut closed = Vec::new();
+    for y in 0..walls.height {
+        for x in 0..walls.width {
+            if x + 1 < walls.width && walls.east[y][x] {
+                closed.push(((x, y), (x + 1, y)));
+            }
+            if y + 1 < walls.height && walls.south[y][x] {
+                closed.push(((x, y), (x, y + 1)));
+            }
+        }
+    }
+    closed.shuffle(rng);
+    let count = (closed.len() as f32 * loop_factor.clamp(0.0, 1.0)).round() as usize;
+    for &(a, b) in closed.iter().take(count) {
+        walls.carve(a, b);
+    }
+}
+
+// Distancia en pasos desde `start` a cada celda (BFS sobre las paredes abiertas)
+fn distances(walls: &Walls, start: (usize, usize)) -> Vec<Vec<usize>> {
+    let mut dist = vec![vec![usize::MAX; walls.width]; walls.height];
+    dist[start.1][start.0] = 0;
+    let mut queue = VecDeque::from([start]);
+    while let Some(cell) = queue.pop_front() {
+        for n in walls.neighbors(cell) {
+            if walls.is_open(cell, n) && dist[n.1][n.0] == usize::MAX {
+                dist[n.1][n.0] = dist[cell.1][cell.0] + 1;
+                queue.push_back(n);
+            }
+        }
+    }
+    dist
+}
+
+fn farthest(walls: &Walls, score: impl Fn((usize, usize)) -> usize, exclude: &[(usize, usize)]) -> (usize, usize) {
+    (0..walls.height)
+        .flat_map(|y| (0..walls.width).map(move |x| (x, y)))
+        .filter(|cell| !exclude.contains(cell))
+        .max_by_key(|&cell| score(cell))
+        .expect("el laberinto tiene al menos 2x2 celdas")
+}
+
+pub struct GeneratedMaze {
+    pub options: GeneratorOptions,
+    pub grid: Vec<Vec<char>>,
+}
+
+pub fn generate(options: &GeneratorOptions) -> GeneratedMaze {
+    let mut rng = StdRng::seed_from_u64(options.seed);
+    let mut walls = Walls::closed(options.width.max(2), options.height.max(2));
+    match options.algorithm {
+        Algorithm::Backtracker => backtracker(&mut walls, &mut rng),
+        Algorithm::Prim => prim(&mut walls, &mut rng),
+        Algorithm::Wilson => wilson(&mut walls, &mut rng),
+    }
+    add_loops(&mut walls, options.loop_factor, &mut rng);
+
+    // Aparición en una esquina, meta en la celda más lejana y la llave en la
+    // celda que obliga a desviarse más del camino directo entre ambas
+    let spawn = (0, walls.height - 1);
+    let from_spawn = distances(&walls, spawn);
+    let goal = farthest(&walls, |(x, y)| from_spawn[y][x], &[spawn]);
+    let from_goal = distances(&walls, goal);
+    let key = farthest(&walls, |(x, y)| from_spawn[y][x] + from_goal[y][x], &[spawn, goal]);
+
+    let mut grid = to_glyphs(&walls);
+    // Mirar hacia un pasillo abierto en lugar de contra la pared
+    let facing = if walls.is_open(spawn, (spawn.0 + 1, spawn.1)) { '>' } else { '^' };
+    grid[spawn.1 * 2 + 1][spawn.0 * 3 + 1] = facing;
+    grid[goal.1 * 2 + 1][goal.0 * 3 + 2] = 'g';
+    grid[key.1 * 2 + 1][key.0 * 3 + 1] = 'k';
+
+    GeneratedMaze { options: *options, grid }
+}
+
+// Cada celda ocupa 3x2 glifos: `+--` arriba y `|  ` en el medio
+fn to_glyphs(walls: &Walls) -> Vec<Vec<char>> {
+    let cols = walls.width * 3 + 1;
+    let rows = walls.height * 2 + 1;
+    let mut grid = vec![vec![' '; cols]; rows];
+    for (j, row) in grid.iter_mut().enumerate() {
+        for (i, glyph) in row.iter_mut().enumerate() {
+            let (cx, cy) = (i / 3, j / 2);
+            *glyph = match (j % 2 == 0, i % 3 == 0) {
+                (true, true) => '+',
+                // Pared horizontal: borde o sur cerrado de la celda de arriba
+                (true, false) => {
+                    if j == 0 || j == rows - 1 || walls.south[cy - 1][cx] { '-' } else { ' ' }
+                }
+                // Pared vertical: borde o este cerrado de la celda de la izquierda
+                (false, true) => {
+                    if i == 0 || i == cols - 1 || walls.east[cy][cx - 1] { '|' } else { ' ' }
+                }
+                (false, false) => ' ',
+            };
+        }
+    }
+    grid
+}
+
+impl GeneratedMaze {
+    pub fn name(&self) -> String {
+        format!("ALEATORIO {} {}", self.options.algorithm.name().to_uppercase(), self.options.seed)
+    }
+
+    // Nombre de archivo al exportarlo desde el menú
+    pub fn file_name(&self) -> String {
+        format!("aleatorio-{}-{}.txt", self.options.algorithm.name(), self.options.seed)
+    }
+
+    // Texto de nivel compatible con `load_maze`, con piso y techo de baldosas/tablones
+    pub fn to_level_text(&self) -> String {
+        let width = self.grid[0].len();
+        let mut text = format!("[level]\nname = {}\n[maze]\n", self.name());
+        for row in &self.grid {
+            text.extend(row.iter());
+            text.push('\n');
+        }
+        text.push_str("[floor]\n");
+        for _ in &self.grid {
+            text.push_str(&".".repeat(width));
+            text.push('\n');
+        }
+        text.push_str("[ceiling]\n");
+        for _ in &self.grid {
+            text.push_str(&"#".repeat(width));
+            text.push('\n');
+        }
+        text
+    }
+
+    pub fn export(&self, path: &str) -> std::io::Result<()> {
+        std::fs::write(path, self.to_level_text())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::maze::{is_walkable, parse_level};
+    use crate::navigation::distance_field;
+
+    fn options(algorithm: Algorithm, seed: u64) -> GeneratorOptions {
+        GeneratorOptions { algorithm, seed, ..GeneratorOptions::default() }
+    }
+
+    #[test]
+    fn same_seed_gives_same_maze() {
+        for algorithm in Algorithm::ALL {
+            let a = generate(&options(algorithm, 7));
+            let b = generate(&options(algorithm, 7));
+            assert_eq!(a.to_level_text(), b.to_level_text(), "{}", algorithm.name());
+        }
+        assert_ne!(generate(&options(Algorithm::Wilson, 7)).grid, generate(&options(Algorithm::Wilson, 8)).grid);
+    }
+
+    #[test]
+    fn every_algorithm_connects_all_cells() {
+        for algorithm in Algorithm::ALL {
+            for seed in 0..5 {
+                let mut rng = StdRng::seed_from_u64(seed);
+                let mut walls = Walls::closed(8, 6);
+                match algorithm {
+                    Algorithm::Backtracker => backtracker(&mut walls, &mut rng),
+                    Algorithm::Prim => prim(&mut walls, &mut rng),
+                    Algorithm::Wilson => wilson(&mut walls, &mut rng),
+                }
+                let dist = distances(&walls, (0, 0));
+                assert!(dist.iter().flatten().all(|&d| d != usize::MAX), "{} semilla {}", algorithm.name(), seed);
+            }
+        }
+    }
+
+    #[test]
+    fn level_text_loads_with_reachable_key_and_goal() {
+        for algorithm in Algorithm::ALL {
+            for seed in 0..5 {
+                let generated = generate(&options(algorithm, seed));
+                let level = parse_level(&generated.to_level_text(), &generated.name())
+                    .unwrap_or_else(|e| panic!("{} semilla {}: {}", algorithm.name(), seed, e));
+                let reachable = distance_field(&level.maze, &[level.spawn]);
+                let keys: Vec<_> = level.keys().collect();
+                assert_eq!(keys.len(), 1);
+                assert!(reachable.reachable(keys[0].1));
+                assert!(reachable.cells().any(|((x, y), _)| level.maze[y][x] == 'g'));
+                // Todas las celdas transitables quedan unidas a la aparición
+                let walkable = level.maze.iter().flatten().filter(|&&c| is_walkable(c)).count();
+                assert_eq!(reachable.cells().count(), walkable);
+            }
+        }
+    }
+}
@@ -0,0 +1,202 @@
+// headless.rs
+// Renderiza un cuadro de un nivel directo a PNG, sin abrir ventana ni usar GPU.
+// Sirve para revisar un cuadro desde la consola y para las pruebas que comparan cuadros:
+//
+//   RayTracer --headless maze1.txt --pos 150,150 --angle 1.57 --out frame.png
+use raylib::prelude::*;
+use rand::prelude::*;
+use rand::rngs::StdRng;
+use crate::framebuffer::Framebuffer;
+use crate::maze::load_maze;
+use crate::player::Player;
+use crate::textures::TextureManager;
+use crate::caster::WallShading;
+use crate::door::Doors;
+use crate::text::Font;
+use crate::{render_frame, scaled_size, default_render_threads, MAX_RENDER_THREADS, level_batteries, get_keys, cell_center, GameState, BLOCK_SIZE};
+use std::f32::consts::PI;
+
+pub struct HeadlessOptions {
+    pub maze_file: String,
+    pub pos: Option<Vector2>, // Por defecto, la aparición del nivel
+    pub angle: Option<f32>,   // Por defecto, la orientación de la aparición
+    pub out: String,
+    pub width: i32,
+    pub height: i32,
+    pub flashlight: bool,
+    pub show_map: bool,
+    pub seed: u64, // Semilla para la posición de las baterías
+    pub render_scale: u32, // Porcentaje de resolución interna del raycasting
+    pub threads: usize,    // Hilos de render (1 = ruta de un solo hilo)
+}
+
+impl HeadlessOptions {
+    pub fn usage() -> &'static str {
+        "uso: RayTracer --headless <maze.txt> [--pos X,Y] [--angle RAD] --out frame.png \
+         [--size 1300x900] [--render-scale 25-100] [--render-threads N] [--flashlight] [--map] [--seed N]"
+    }
+
+    pub fn from_args(args: &[String]) -> Result<Self, String> {
+        let mut options = HeadlessOptions {
+            maze_file: String::new(),
+            pos: None,
+            angle: None,
+            out: String::from("frame.png"),
+            width: 1300,
+            height: 900,
+            flashlight: false,
+            show_map: false,
+            seed: 0,
+            render_scale: 100,
+            threads: default_render_threads(),
+        };
+
+        let mut iter = args.iter().skip(1);
+        while let Some(arg) = iter.next() {
+            match arg.as_str() {
+                "--headless" => options.maze_file = next_value(&mut iter, arg)?.to_string(),
+                "--pos" => {
+                    let (x, y) = parse_pair(next_value(&mut iter, arg)?, ',')?;
+                    options.pos = Some(Vector2::new(x, y));
+                }
+                "--angle" => options.angle = Some(parse_number(next_value(&mut iter, arg)?)?),
+                "--out" => options.out = next_value(&mut iter, arg)?.to_string(),
+                "--size" => {
+                    let (w, h) = parse_size(next_value(&mut iter, arg)?)?;
+                    options.width = w;
+                    options.height = h;
+                }
+                "--flashlight" => options.flashlight = true,
+                "--map" => options.show_map = true,
+                "--seed" => options.seed = parse_number(next_value(&mut iter, arg)?)?,
+                "--render-threads" => options.threads = parse_number::<usize>(next_value(&mut iter, arg)?)?.clamp(1, MAX_RENDER_THREADS),
+                "--render-scale" => options.render_scale = parse_number(next_value(&mut iter, arg)?.trim_end_matches('%'))?,
+                other => return Err(format!("argumento desconocido: {}", other)),
+            }
+        }
+
+        if options.maze_file.is_empty() {
+            return Err(String::from("falta el archivo del laberinto"));
+        }
+        Ok(options)
+    }
+}
+
+fn next_value<'a>(iter: &mut impl Iterator<Item = &'a String>, flag: &str) -> Result<&'a str, String> {
+    iter.next().map(|s| s.as_str()).ok_or_else(|| format!("falta el valor de {}", flag))
+}
+
+fn parse_number<T: std::str::FromStr>(value: &str) -> Result<T, String> {
+    value.trim().parse().map_err(|_| format!("valor inválido: {}", value))
+}
+
+fn parse_pair<T: std::str::FromStr>(value: &str, separator: char) -> Result<(T, T), String> {
+    let (a, b) = value
+        .split_once(separator)
+        .ok_or_else(|| format!("se esperaba A{}B: {}", separator, value))?;
+    Ok((parse_number(a)?, parse_number(b)?))
+}
+
+// Ancho y alto en píxeles enteros y positivos
+fn parse_size(value: &str) -> Result<(i32, i32), String> {
+    parse_pair::<i32>(value, 'x')
+        .ok()
+        .filter(|&(w, h)| w > 0 && h > 0)
+        .ok_or_else(|| format!("tamaño inválido: {}", value))
+}
+
+pub fn render_to_png(options: &HeadlessOptions) -> Result<(), String> {
+    render(options)?
+        .save_png(&options.out)
+        .map_err(|e| format!("no se pudo escribir {}: {}", options.out, e))
+}
+
+// Cuadro completo (escena, HUD y minimapa) tal como se vería en la ventana
+pub fn render(options: &HeadlessOptions) -> Result<Framebuffer, String> {
+    let level = load_maze(&options.maze_file).map_err(|e| format!("{}: {}", options.maze_file, e))?;
+    let texture_cache = TextureManager::new_headless();
+    let font = Font::new();
+
+    let player = Player::new(
+        options.pos.unwrap_or_else(|| cell_center(level.spawn, BLOCK_SIZE)),
+        options.angle.unwrap_or(level.spawn_angle),
+    );
+
+    let mut game_state = GameState::new();
+    game_state.reset(&level);
+    game_state.flashlight_on = options.flashlight;
+
+    let mut rng = StdRng::seed_from_u64(options.seed);
+    let batteries = level_batteries(&level, BLOCK_SIZE, &mut rng);
+    let keys = get_keys(&level, BLOCK_SIZE);
+
+    let mut framebuffer = Framebuffer::new(options.width, options.height, Color::new(50, 50, 100, 255));
+    framebuffer.set_background_color(Color::new(80, 80, 200, 255));
+    framebuffer.clear();
+
+    let (scene_width, scene_height) = scaled_size(options.width, options.height, options.render_scale);
+    let mut scene = Framebuffer::new(scene_width, scene_height, Color::BLACK);
+
+    render_frame(
+        &mut framebuffer,
+        &mut scene,
+        &level.maze,
+        &Doors::from_maze(&level.maze),
+        &level.surfaces,
+        BLOCK_SIZE,
+        &player,
+        &texture_cache,
+        &WallShading::default(),
+        &game_state,
+        &keys,
+        &batteries,
+        &font,
+        options.show_map,
+        150,
+        options.threads,
+    );
+
+    Ok(framebuffer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options(threads: usize, flashlight: bool) -> HeadlessOptions {
+        let args: Vec<String> = ["RayTracer", "--headless", concat!(env!("CARGO_MANIFEST_DIR"), "/levels/maze1.txt"), "--size", "320x200"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let mut options = HeadlessOptions::from_args(&args).unwrap();
+        options.threads = threads;
+        options.flashlight = flashlight;
+        options
+    }
+
+    fn with_size(size: &str) -> Result<HeadlessOptions, String> {
+        let args: Vec<String> = ["RayTracer", "--headless", "maze1.txt", "--size", size]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        HeadlessOptions::from_args(&args)
+    }
+
+    #[test]
+    fn size_takes_positive_integers() {
+        let options = with_size("640x480").unwrap();
+        assert_eq!((options.width, options.height), (640, 480));
+        for size in ["1.9x1.9", "0x0", "-5x10", "640x0", "640", "AxB"] {
+            assert_eq!(with_size(size).err(), Some(format!("tamaño inválido: {}", size)));
+        }
+    }
+
+    #[test]
+    fn threads_do_not_change_the_frame() {
+        for flashlight in [false, true] {
+            let single = render(&options(1, flashlight)).unwrap();
+            let parallel = render(&options(MAX_RENDER_THREADS, flashlight)).unwrap();
+            assert!(single.pixels() == parallel.pixels(), "linterna: {}", flashlight);
+        }
+    }
+}
@@ -0,0 +1,607 @@
+// input.rs
+// Capa de entrada: traduce teclas, botones y ejes del ratón y del control
+// (gamepad) a acciones con nombre. El juego solo pregunta por acciones, así
+// los controles se pueden reasignar desde un archivo o desde la pantalla de
+// controles del menú.
+use raylib::prelude::*;
+use std::collections::HashMap;
+use std::fmt;
+use std::io;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Action {
+    MoveForward,
+    MoveBackward,
+    StrafeLeft,
+    StrafeRight,
+    TurnLeft,
+    TurnRight,
+    Look, // Eje: giro con el ratón
+    Sprint,
+    ToggleFlashlight,
+    ShowMap,
+    Pause,
+    MenuUp,
+    MenuDown,
+    MenuLeft,
+    MenuRight,
+    Confirm,
+    Back,
+    Restart,
+    RenderScale,
+    Controls,
+    Options,
+    ExportLevel, // Guardar el último nivel aleatorio en la carpeta de niveles
+}
+
+impl Action {
+    pub const ALL: [Action; 22] = [
+        Action::MoveForward,
+        Action::MoveBackward,
+        Action::StrafeLeft,
+        Action::StrafeRight,
+        Action::TurnLeft,
+        Action::TurnRight,
+        Action::Look,
+        Action::Sprint,
+        Action::ToggleFlashlight,
+        Action::ShowMap,
+        Action::Pause,
+        Action::MenuUp,
+        Action::MenuDown,
+        Action::MenuLeft,
+        Action::MenuRight,
+        Action::Confirm,
+        Action::Back,
+        Action::Restart,
+        Action::RenderScale,
+        Action::Controls,
+        Action::Options,
+        Action::ExportLevel,
+    ];
+
+    // Nombre en el archivo de controles
+    pub fn name(self) -> &'static str {
+        match self {
+            Action::MoveForward => "move_forward",
+            Action::MoveBackward => "move_backward",
+            Action::StrafeLeft => "strafe_left",
+            Action::StrafeRight => "strafe_right",
+            Action::TurnLeft => "turn_left",
+            Action::TurnRight => "turn_right",
+            Action::Look => "look",
+            Action::Sprint => "sprint",
+            Action::ToggleFlashlight => "flashlight",
+            Action::ShowMap => "map",
+            Action::Pause => "pause",
+            Action::MenuUp => "menu_up",
+            Action::MenuDown => "menu_down",
+            Action::MenuLeft => "menu_left",
+            Action::MenuRight => "menu_right",
+            Action::Confirm => "confirm",
+            Action::Back => "back",
+            Action::Restart => "restart",
+            Action::RenderScale => "render_scale",
+            Action::Controls => "controls",
+            Action::Options => "options",
+            Action::ExportLevel => "export_level",
+        }
+    }
+
+    // Texto en la pantalla de controles
+    pub fn label(self) -> &'static str {
+        match self {
+            Action::MoveForward => "AVANZAR",
+            Action::MoveBackward => "RETROCEDER",
+            Action::StrafeLeft => "IR A LA IZQUIERDA",
+            Action::StrafeRight => "IR A LA DERECHA",
+            Action::TurnLeft => "GIRAR A LA IZQUIERDA",
+            Action::TurnRight => "GIRAR A LA DERECHA",
+            Action::Look => "MIRAR",
+            Action::Sprint => "CORRER",
+            Action::ToggleFlashlight => "LINTERNA",
+            Action::ShowMap => "MAPA",
+            Action::Pause => "PAUSA",
+            Action::MenuUp => "MENU: ARRIBA",
+            Action::MenuDown => "MENU: ABAJO",
+            Action::MenuLeft => "MENU: IZQUIERDA",
+            Action::MenuRight => "MENU: DERECHA",
+            Action::Confirm => "ACEPTAR",
+            Action::Back => "VOLVER",
+            Action::Restart => "JUGAR OTRA VEZ",
+            Action::RenderScale => "RESOLUCION",
+            Action::Controls => "CONTROLES",
+            Action::Options => "OPCIONES",
+            Action::ExportLevel => "EXPORTAR NIVEL",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Action::ALL.into_iter().find(|a| a.name() == name)
+    }
+
+    // Las acciones de botón se pueden reasignar en el menú; los ejes solo en el archivo
+    pub fn is_axis(self) -> bool {
+        self == Action::Look
+    }
+
+    // Acciones que se leen en los menús y pantallas de fin; el resto, jugando.
+    // Una misma entrada puede servir a una acción de cada grupo (ESC pausa y vuelve).
+    pub fn is_menu(self) -> bool {
+        matches!(
+            self,
+            Action::MenuUp
+                | Action::MenuDown
+                | Action::MenuLeft
+                | Action::MenuRight
+                | Action::Confirm
+                | Action::Back
+                | Action::Restart
+                | Action::RenderScale
+                | Action::Controls
+                | Action::Options
+                | Action::ExportLevel
+        )
+    }
+}
+
+// Control que se lee (el primero conectado)
+const GAMEPAD: i32 = 0;
+
+// Dirección de una palanca del control; da un valor analógico de 0 a 1
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StickDirection {
+    pub axis: GamepadAxis,
+    pub positive: bool, // Derecha/abajo si es true, izquierda/arriba si no
+}
+
+// Entrada física asociada a una acción
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Binding {
+    Key(KeyboardKey),
+    Mouse(MouseButton),
+    MouseX, // Movimiento horizontal del ratón (solo para ejes)
+    MouseY,
+    Pad(GamepadButton),
+    Stick(StickDirection),
+}
+
+const KEY_NAMES: [(&str, KeyboardKey); 50] = [
+    ("A", KeyboardKey::KEY_A),
+    ("B", KeyboardKey::KEY_B),
+    ("C", KeyboardKey::KEY_C),
+    ("D", KeyboardKey::KEY_D),
+    ("E", KeyboardKey::KEY_E),
+    ("F", KeyboardKey::KEY_F),
+    ("G", KeyboardKey::KEY_G),
+    ("H", KeyboardKey::KEY_H),
+    ("I", KeyboardKey::KEY_I),
+    ("J", KeyboardKey::KEY_J),
+    ("K", KeyboardKey::KEY_K),
+    ("L", KeyboardKey::KEY_L),
+    ("M", KeyboardKey::KEY_M),
+    ("N", KeyboardKey::KEY_N),
+    ("O", KeyboardKey::KEY_O),
+    ("P", KeyboardKey::KEY_P),
+    ("Q", KeyboardKey::KEY_Q),
+    ("R", KeyboardKey::KEY_R),
+    ("S", KeyboardKey::KEY_S),
+    ("T", KeyboardKey::KEY_T),
+    ("U", KeyboardKey::KEY_U),
+    ("V", KeyboardKey::KEY_V),
+    ("W", KeyboardKey::KEY_W),
+    ("X", KeyboardKey::KEY_X),
+    ("Y", KeyboardKey::KEY_Y),
+    ("Z", KeyboardKey::KEY_Z),
+    ("0", KeyboardKey::KEY_ZERO),
+    ("1", KeyboardKey::KEY_ONE),
+    ("2", KeyboardKey::KEY_TWO),
+    ("3", KeyboardKey::KEY_THREE),
+    ("4", KeyboardKey::KEY_FOUR),
+    ("5", KeyboardKey::KEY_FIVE),
+    ("6", KeyboardKey::KEY_SIX),
+    ("7", KeyboardKey::KEY_SEVEN),
+    ("8", KeyboardKey::KEY_EIGHT),
+    ("9", KeyboardKey::KEY_NINE),
+    ("UP", KeyboardKey::KEY_UP),
+    ("DOWN", KeyboardKey::KEY_DOWN),
+    ("LEFT", KeyboardKey::KEY_LEFT),
+    ("RIGHT", KeyboardKey::KEY_RIGHT),
+    ("SPACE", KeyboardKey::KEY_SPACE),
+    ("ENTER", KeyboardKey::KEY_ENTER),
+    ("ESCAPE", KeyboardKey::KEY_ESCAPE),
+    ("TAB", KeyboardKey::KEY_TAB),
+    ("BACKSPACE", KeyboardKey::KEY_BACKSPACE),
+    ("LEFT_SHIFT", KeyboardKey::KEY_LEFT_SHIFT),
+    ("RIGHT_SHIFT", KeyboardKey::KEY_RIGHT_SHIFT),
+    ("LEFT_CONTROL", KeyboardKey::KEY_LEFT_CONTROL),
+    ("RIGHT_CONTROL", KeyboardKey::KEY_RIGHT_CONTROL),
+    ("LEFT_ALT", KeyboardKey::KEY_LEFT_ALT),
+];
+
+const MOUSE_NAMES: [(&str, MouseButton); 3] = [
+    ("MOUSE_LEFT", MouseButton::MOUSE_BUTTON_LEFT),
+    ("MOUSE_RIGHT", MouseButton::MOUSE_BUTTON_RIGHT),
+    ("MOUSE_MIDDLE", MouseButton::MOUSE_BUTTON_MIDDLE),
+];
+
+const PAD_NAMES: [(&str, GamepadButton); 16] = [
+    ("PAD_UP", GamepadButton::GAMEPAD_BUTTON_LEFT_FACE_UP), // Cruceta
+    ("PAD_DOWN", GamepadButton::GAMEPAD_BUTTON_LEFT_FACE_DOWN),
+    ("PAD_LEFT", GamepadButton::GAMEPAD_BUTTON_LEFT_FACE_LEFT),
+    ("PAD_RIGHT", GamepadButton::GAMEPAD_BUTTON_LEFT_FACE_RIGHT),
+    ("PAD_A", GamepadButton::GAMEPAD_BUTTON_RIGHT_FACE_DOWN), // Botones según la posición en un control Xbox
+    ("PAD_B", GamepadButton::GAMEPAD_BUTTON_RIGHT_FACE_RIGHT),
+    ("PAD_X", GamepadButton::GAMEPAD_BUTTON_RIGHT_FACE_LEFT),
+    ("PAD_Y", GamepadButton::GAMEPAD_BUTTON_RIGHT_FACE_UP),
+    ("PAD_LB", GamepadButton::GAMEPAD_BUTTON_LEFT_TRIGGER_1),
+    ("PAD_RB", GamepadButton::GAMEPAD_BUTTON_RIGHT_TRIGGER_1),
+    ("PAD_LT", GamepadButton::GAMEPAD_BUTTON_LEFT_TRIGGER_2),
+    ("PAD_RT", GamepadButton::GAMEPAD_BUTTON_RIGHT_TRIGGER_2),
+    ("PAD_SELECT", GamepadButton::GAMEPAD_BUTTON_MIDDLE_LEFT),
+    ("PAD_START", GamepadButton::GAMEPAD_BUTTON_MIDDLE_RIGHT),
+    ("PAD_L3", GamepadButton::GAMEPAD_BUTTON_LEFT_THUMB), // Palancas presionadas
+    ("PAD_R3", GamepadButton::GAMEPAD_BUTTON_RIGHT_THUMB),
+];
+
+const STICK_NAMES: [(&str, StickDirection); 8] = [
+    ("PAD_LSTICK_UP", StickDirection { axis: GamepadAxis::GAMEPAD_AXIS_LEFT_Y, positive: false }),
+    ("PAD_LSTICK_DOWN", StickDirection { axis: GamepadAxis::GAMEPAD_AXIS_LEFT_Y, positive: true }),
+    ("PAD_LSTICK_LEFT", StickDirection { axis: GamepadAxis::GAMEPAD_AXIS_LEFT_X, positive: false }),
+    ("PAD_LSTICK_RIGHT", StickDirection { axis: GamepadAxis::GAMEPAD_AXIS_LEFT_X, positive: true }),
+    ("PAD_RSTICK_UP", StickDirection { axis: GamepadAxis::GAMEPAD_AXIS_RIGHT_Y, positive: false }),
+    ("PAD_RSTICK_DOWN", StickDirection { axis: GamepadAxis::GAMEPAD_AXIS_RIGHT_Y, positive: true }),
+    ("PAD_RSTICK_LEFT", StickDirection { axis: GamepadAxis::GAMEPAD_AXIS_RIGHT_X, positive: false }),
+    ("PAD_RSTICK_RIGHT", StickDirection { axis: GamepadAxis::GAMEPAD_AXIS_RIGHT_X, positive: true }),
+];
+
+// Respuesta de las palancas: zona muerta, curva y sensibilidad de la mirada
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StickResponse {
+    pub deadzone: f32,         // Inclinación (0..1) por debajo de la cual se ignora
+    pub curve: f32,            // Exponente: >1 da más precisión cerca del centro
+    pub look_sensitivity: f32, // Multiplicador de la palanca derecha
+}
+
+impl Default for StickResponse {
+    fn default() -> Self {
+        StickResponse {
+            deadzone: 0.2,
+            curve: 2.0,
+            look_sensitivity: 1.0,
+        }
+    }
+}
+
+impl StickResponse {
+    // Inclinación cruda (-1..1) hacia `positive` convertida a 0..1
+    pub fn apply(&self, raw: f32, positive: bool) -> f32 {
+        let tilt = if positive { raw } else { -raw };
+        if tilt <= self.deadzone {
+            return 0.0;
+        }
+        let t = ((tilt - self.deadzone) / (1.0 - self.deadzone).max(f32::EPSILON)).min(1.0);
+        t.powf(self.curve)
+    }
+}
+
+impl Binding {
+    pub fn name(self) -> &'static str {
+        match self {
+            Binding::Key(key) => KEY_NAMES.iter().find(|(_, k)| *k == key).map_or("?", |(name, _)| name),
+            Binding::Mouse(button) => MOUSE_NAMES.iter().find(|(_, b)| *b == button).map_or("?", |(name, _)| name),
+            Binding::MouseX => "MOUSE_X",
+            Binding::MouseY => "MOUSE_Y",
+            Binding::Pad(button) => PAD_NAMES.iter().find(|(_, b)| *b == button).map_or("?", |(name, _)| name),
+            Binding::Stick(dir) => STICK_NAMES.iter().find(|(_, d)| *d == dir).map_or("?", |(name, _)| name),
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "MOUSE_X" => Some(Binding::MouseX),
+            "MOUSE_Y" => Some(Binding::MouseY),
+            _ => KEY_NAMES
+                .iter()
+                .find(|(n, _)| *n == name)
+                .map(|&(_, key)| Binding::Key(key))
+                .or_else(|| MOUSE_NAMES.iter().find(|(n, _)| *n == name).map(|&(_, b)| Binding::Mouse(b)))
+                .or_else(|| PAD_NAMES.iter().find(|(n, _)| *n == name).map(|&(_, b)| Binding::Pad(b)))
+                .or_else(|| STICK_NAMES.iter().find(|(n, _)| *n == name).map(|&(_, d)| Binding::Stick(d))),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ControlsError {
+    Io(io::Error),
+    InvalidLine { line: String },
+}
+
+impl fmt::Display for ControlsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ControlsError::Io(e) => write!(f, "no se pudo leer el archivo de controles: {}", e),
+            ControlsError::InvalidLine { line } => write!(f, "linea de controles invalida: {}", line),
+        }
+    }
+}
+
+impl std::error::Error for ControlsError {}
+
+impl From<io::Error> for ControlsError {
+    fn from(e: io::Error) -> Self {
+        ControlsError::Io(e)
+    }
+}
+
+pub struct InputMap {
+    bindings: HashMap<Action, Vec<Binding>>,
+    pub sticks: StickResponse,
+}
+
+impl Default for InputMap {
+    fn default() -> Self {
+        use GamepadAxis::*;
+        use GamepadButton::*;
+        use KeyboardKey::*;
+        let key = Binding::Key;
+        let pad = Binding::Pad;
+        let stick = |axis, positive| Binding::Stick(StickDirection { axis, positive });
+        let bindings = Action::ALL
+            .into_iter()
+            .map(|action| {
+                let defaults = match action {
+                    Action::MoveForward => vec![key(KEY_W), key(KEY_UP), stick(GAMEPAD_AXIS_LEFT_Y, false)],
+                    Action::MoveBackward => vec![key(KEY_S), key(KEY_DOWN), stick(GAMEPAD_AXIS_LEFT_Y, true)],
+                    Action::StrafeLeft => vec![stick(GAMEPAD_AXIS_LEFT_X, false)],
+                    Action::StrafeRight => vec![stick(GAMEPAD_AXIS_LEFT_X, true)],
+                    Action::TurnLeft => vec![key(KEY_A), key(KEY_LEFT), stick(GAMEPAD_AXIS_RIGHT_X, false)],
+                    Action::TurnRight => vec![key(KEY_D), key(KEY_RIGHT), stick(GAMEPAD_AXIS_RIGHT_X, true)],
+                    Action::Look => vec![Binding::MouseX],
+                    Action::Sprint => vec![key(KEY_LEFT_SHIFT), pad(GAMEPAD_BUTTON_LEFT_THUMB)],
+                    Action::ToggleFlashlight => vec![key(KEY_E), pad(GAMEPAD_BUTTON_RIGHT_FACE_LEFT)],
+                    Action::ShowMap => vec![key(KEY_M), pad(GAMEPAD_BUTTON_MIDDLE_LEFT)],
+                    Action::Pause => vec![key(KEY_ESCAPE), pad(GAMEPAD_BUTTON_MIDDLE_RIGHT)],
+                    Action::MenuUp => vec![key(KEY_UP), pad(GAMEPAD_BUTTON_LEFT_FACE_UP)],
+                    Action::MenuDown => vec![key(KEY_DOWN), pad(GAMEPAD_BUTTON_LEFT_FACE_DOWN)],
+                    Action::MenuLeft => vec![key(KEY_LEFT), pad(GAMEPAD_BUTTON_LEFT_FACE_LEFT)],
+                    Action::MenuRight => vec![key(KEY_RIGHT), pad(GAMEPAD_BUTTON_LEFT_FACE_RIGHT)],
+                    Action::Confirm => vec![key(KEY_ENTER), pad(GAMEPAD_BUTTON_RIGHT_FACE_DOWN)],
+                    Action::Back => vec![key(KEY_ESCAPE), pad(GAMEPAD_BUTTON_RIGHT_FACE_RIGHT)],
+                    Action::Restart => vec![key(KEY_SPACE), pad(GAMEPAD_BUTTON_MIDDLE_RIGHT)],
+                    Action::RenderScale => vec![key(KEY_TAB)],
+                    Action::Controls => vec![key(KEY_C)],
+                    Action::Options => vec![key(KEY_O), pad(GAMEPAD_BUTTON_RIGHT_FACE_UP)],
+                    Action::ExportLevel => vec![key(KEY_X)],
+                };
+                (action, defaults)
+            })
+            .collect();
+        InputMap { bindings, sticks: StickResponse::default() }
+    }
+}
+
+impl InputMap {
+    // Controles del archivo; las acciones que no aparecen conservan su valor por defecto
+    pub fn load(path: &str) -> Result<Self, ControlsError> {
+        let text = std::fs::read_to_string(path)?;
+        Self::parse(&text)
+    }
+
+    // Líneas `accion = ENTRADA, ENTRADA`; `;` inicia un comentario y `none` deja la acción sin entradas.
+    // `pad_deadzone`, `pad_curve` y `pad_look_sensitivity` ajustan las palancas.
+    pub fn parse(text: &str) -> Result<Self, ControlsError> {
+        let mut map = InputMap::default();
+        for line in text.lines() {
+            let trimmed = line.split(';').next().unwrap_or("").trim();
+            if trimmed.is_empty() {
+                continue;
+            }
+            let invalid = || ControlsError::InvalidLine { line: trimmed.to_string() };
+            let (name, value) = trimmed.split_once('=').ok_or_else(invalid)?;
+            let (name, value) = (name.trim(), value.trim());
+            let stick_setting = match name {
+                "pad_deadzone" => Some(&mut map.sticks.deadzone),
+                "pad_curve" => Some(&mut map.sticks.curve),
+                "pad_look_sensitivity" => Some(&mut map.sticks.look_sensitivity),
+                _ => None,
+            };
+            if let Some(setting) = stick_setting {
+                *setting = value.parse().ok().filter(|v: &f32| *v >= 0.0).ok_or_else(invalid)?;
+                continue;
+            }
+            let action = Action::from_name(name).ok_or_else(invalid)?;
+            let bindings = if value == "none" {
+                Vec::new()
+            } else {
+                value
+                    .split(',')
+                    .map(|input| Binding::from_name(input.trim()).ok_or_else(invalid))
+                    .collect::<Result<Vec<_>, _>>()?
+            };
+            map.bindings.insert(action, bindings);
+        }
+        Ok(map)
+    }
+
+    pub fn save(&self, path: &str) -> io::Result<()> {
+        let mut text = String::from("; Controles: accion = entradas separadas por comas (o none)\n");
+        for action in Action::ALL {
+            let names: Vec<&str> = self.bindings(action).iter().map(|b| b.name()).collect();
+            let value = if names.is_empty() { "none".to_string() } else { names.join(", ") };
+            text.push_str(&format!("{} = {}\n", action.name(), value));
+        }
+        text.push_str(&format!("pad_deadzone = {}\n", self.sticks.deadzone));
+        text.push_str(&format!("pad_curve = {}\n", self.sticks.curve));
+        text.push_str(&format!("pad_look_sensitivity = {}\n", self.sticks.look_sensitivity));
+        std::fs::write(path, text)
+    }
+
+    pub fn bindings(&self, action: Action) -> &[Binding] {
+        self.bindings.get(&action).map_or(&[], Vec::as_slice)
+    }
+
+    // Pone `binding` como entrada principal de la acción (las demás se conservan)
+    // y se la quita a las otras acciones del mismo grupo, para que una tecla no
+    // dispare dos acciones a la vez
+    pub fn set_primary(&mut self, action: Action, binding: Binding) {
+        for (&other, bindings) in self.bindings.iter_mut() {
+            if other.is_menu() == action.is_menu() {
+                bindings.retain(|&b| b != binding);
+            }
+        }
+        self.bindings.entry(action).or_default().insert(0, binding);
+    }
+
+    // A/D pasan de girar a desplazarse de lado
+    pub fn use_strafe_keys(&mut self) {
+        let (a, d) = (Binding::Key(KeyboardKey::KEY_A), Binding::Key(KeyboardKey::KEY_D));
+        for action in [Action::TurnLeft, Action::TurnRight] {
+            if let Some(bindings) = self.bindings.get_mut(&action) {
+                bindings.retain(|&b| b != a && b != d);
+            }
+        }
+        self.set_primary(Action::StrafeLeft, a);
+        self.set_primary(Action::StrafeRight, d);
+    }
+
+    // Inclinación (0..1) de una palanca en una dirección, ya con la respuesta aplicada
+    fn stick(&self, window: &RaylibHandle, dir: StickDirection) -> f32 {
+        if !window.is_gamepad_available(GAMEPAD) {
+            return 0.0;
+        }
+        let value = self.sticks.apply(window.get_gamepad_axis_movement(GAMEPAD, dir.axis), dir.positive);
+        let look = matches!(dir.axis, GamepadAxis::GAMEPAD_AXIS_RIGHT_X | GamepadAxis::GAMEPAD_AXIS_RIGHT_Y);
+        if look { value * self.sticks.look_sensitivity } else { value }
+    }
+
+    fn pad_down(window: &RaylibHandle, button: GamepadButton) -> bool {
+        window.is_gamepad_available(GAMEPAD) && window.is_gamepad_button_down(GAMEPAD, button)
+    }
+
+    // Intensidad de la acción: 1 con teclas y botones, proporcional con palancas
+    pub fn value(&self, window: &RaylibHandle, action: Action) -> f32 {
+        self.bindings(action)
+            .iter()
+            .map(|&binding| match binding {
+                Binding::Key(key) => if window.is_key_down(key) { 1.0 } else { 0.0 },
+                Binding::Mouse(button) => if window.is_mouse_button_down(button) { 1.0 } else { 0.0 },
+                Binding::Pad(button) => if Self::pad_down(window, button) { 1.0 } else { 0.0 },
+                Binding::Stick(dir) => self.stick(window, dir),
+                Binding::MouseX | Binding::MouseY => 0.0,
+            })
+            .fold(0.0, f32::max)
+    }
+
+    // ¿Alguna entrada de la acción está presionada? (una palanca cuenta pasada la mitad)
+    pub fn down(&self, window: &RaylibHandle, action: Action) -> bool {
+        self.value(window, action) >= 0.5
+    }
+
+    // ¿Alguna entrada de la acción se presionó en este cuadro?
+    pub fn pressed(&self, window: &RaylibHandle, action: Action) -> bool {
+        self.bindings(action).iter().any(|&binding| match binding {
+            Binding::Key(key) => window.is_key_pressed(key),
+            Binding::Mouse(button) => window.is_mouse_button_pressed(button),
+            Binding::Pad(button) => window.is_gamepad_available(GAMEPAD) && window.is_gamepad_button_pressed(GAMEPAD, button),
+            Binding::MouseX | Binding::MouseY | Binding::Stick(_) => false,
+        })
+    }
+
+    // Valor de un eje en este cuadro (píxeles de ratón)
+    pub fn axis(&self, window: &RaylibHandle, action: Action) -> f32 {
+        let delta = window.get_mouse_delta();
+        self.bindings(action)
+            .iter()
+            .map(|&binding| match binding {
+                Binding::MouseX => delta.x,
+                Binding::MouseY => delta.y,
+                _ => 0.0,
+            })
+            .sum()
+    }
+}
+
+// Primera tecla o botón (del ratón o del control) presionado en este cuadro,
+// para reasignar una acción
+pub fn capture_binding(window: &mut RaylibHandle) -> Option<Binding> {
+    if let Some(key) = window.get_key_pressed() {
+        let binding = Binding::Key(key);
+        // Teclas sin nombre no se podrían guardar en el archivo
+        return (binding.name() != "?").then_some(binding);
+    }
+    if window.is_gamepad_available(GAMEPAD) {
+        let pad = PAD_NAMES.iter().find(|(_, button)| window.is_gamepad_button_pressed(GAMEPAD, *button));
+        if let Some(&(_, button)) = pad {
+            return Some(Binding::Pad(button));
+        }
+    }
+    MOUSE_NAMES
+        .iter()
+        .find(|(_, button)| window.is_mouse_button_pressed(*button))
+        .map(|&(_, button)| Binding::Mouse(button))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_bindings_have_names() {
+        let input = InputMap::default();
+        for action in Action::ALL {
+            for &binding in input.bindings(action) {
+                assert_eq!(Binding::from_name(binding.name()), Some(binding), "{}", action.name());
+            }
+        }
+    }
+
+    #[test]
+    fn defaults_do_not_share_inputs_within_a_group() {
+        let input = InputMap::default();
+        for a in Action::ALL {
+            for b in Action::ALL.into_iter().filter(|&b| b != a && b.is_menu() == a.is_menu()) {
+                for binding in input.bindings(a) {
+                    assert!(!input.bindings(b).contains(binding), "{} y {}: {}", a.name(), b.name(), binding.name());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn set_primary_takes_the_input_from_its_group_only() {
+        let mut input = InputMap::default();
+        let escape = Binding::Key(KeyboardKey::KEY_ESCAPE);
+        let enter = Binding::Key(KeyboardKey::KEY_ENTER);
+        // ENTER deja de aceptar en los menús; ESC sigue pausando en el juego
+        input.set_primary(Action::Back, enter);
+        assert_eq!(input.bindings(Action::Back)[0], enter);
+        assert!(!input.bindings(Action::Confirm).contains(&enter));
+        assert!(input.bindings(Action::Back).contains(&escape));
+        assert!(input.bindings(Action::Pause).contains(&escape));
+        // Jugando, E pasa de la linterna al mapa
+        let e = Binding::Key(KeyboardKey::KEY_E);
+        input.set_primary(Action::ShowMap, e);
+        assert!(!input.bindings(Action::ToggleFlashlight).contains(&e));
+    }
+
+    #[test]
+    fn saved_controls_parse_back() {
+        let mut input = InputMap::default();
+        input.set_primary(Action::Sprint, Binding::Pad(GamepadButton::GAMEPAD_BUTTON_RIGHT_TRIGGER_2));
+        input.set_primary(Action::Look, Binding::MouseY);
+        input.bindings.insert(Action::ShowMap, Vec::new());
+        input.sticks.deadzone = 0.35;
+        let path = std::env::temp_dir().join(format!("raycaster-controls-{}.txt", std::process::id()));
+        let path = path.to_string_lossy();
+        input.save(&path).unwrap();
+        let loaded = InputMap::load(&path);
+        let _ = std::fs::remove_file(&*path);
+        let loaded = loaded.unwrap();
+        for action in Action::ALL {
+            assert_eq!(loaded.bindings(action), input.bindings(action), "{}", action.name());
+        }
+        assert_eq!(loaded.sticks, input.sticks);
+    }
+}
@@ -0,0 +1,65 @@
+// inventory.rs
+// Objetos que el jugador recoge durante un nivel. Cada recogida además queda
+// anotada como evento para que el bucle principal reproduzca su sonido.
+use crate::key::KeyColor;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Item {
+    Key(KeyColor),
+    Battery,
+}
+
+impl Item {
+    // Textura que se usa como ícono en el HUD
+    pub fn icon(self) -> char {
+        match self {
+            Item::Key(color) => color.key_glyph(),
+            Item::Battery => 'b',
+        }
+    }
+
+    pub fn pickup_sound(self) -> &'static str {
+        match self {
+            Item::Key(_) => "assets/sounds/key_pickup.wav",
+            Item::Battery => "assets/sounds/battery_pickup.mp3",
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct Inventory {
+    items: Vec<(Item, u32)>, // En orden de recogida, para el HUD
+    pickups: Vec<Item>,      // Recogidas aún no atendidas
+}
+
+impl Inventory {
+    pub fn add(&mut self, item: Item) {
+        match self.items.iter_mut().find(|(held, _)| *held == item) {
+            Some((_, count)) => *count += 1,
+            None => self.items.push((item, 1)),
+        }
+        self.pickups.push(item);
+    }
+
+    pub fn count(&self, item: Item) -> u32 {
+        self.items.iter().find(|(held, _)| *held == item).map_or(0, |&(_, count)| count)
+    }
+
+    pub fn has_key(&self, color: KeyColor) -> bool {
+        self.count(Item::Key(color)) > 0
+    }
+
+    pub fn items(&self) -> impl Iterator<Item = (Item, u32)> + '_ {
+        self.items.iter().copied()
+    }
+
+    // Recogidas desde la última llamada (para los efectos de sonido)
+    pub fn take_pickups(&mut self) -> Vec<Item> {
+        std::mem::take(&mut self.pickups)
+    }
+
+    pub fn clear(&mut self) {
+        self.items.clear();
+        self.pickups.clear();
+    }
+}
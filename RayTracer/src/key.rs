@@ -1,20 +1,93 @@
 use raylib::prelude::*;
-use crate::textures::TextureManager;
+use crate::sprite::{Anchor, Sprite};
+
+// Color de una llave; cada puerta cerrada con llave pide un color.
+// La dorada es la que abre la salida.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum KeyColor {
+    Gold,
+    Red,
+    Blue,
+}
+
+impl KeyColor {
+    pub const ALL: [KeyColor; 3] = [KeyColor::Gold, KeyColor::Red, KeyColor::Blue];
+
+    // Glifo de la llave en el laberinto (y clave de su textura)
+    pub fn key_glyph(self) -> char {
+        match self {
+            KeyColor::Gold => 'k',
+            KeyColor::Red => 'r',
+            KeyColor::Blue => 'u',
+        }
+    }
+
+    // Glifo de la puerta que abre: la misma letra en mayúscula
+    pub fn gate_glyph(self) -> char {
+        self.key_glyph().to_ascii_uppercase()
+    }
+
+    // Color en el HUD y los mapas
+    pub fn color(self) -> Color {
+        match self {
+            KeyColor::Gold => Color::GOLD,
+            KeyColor::Red => Color::RED,
+            KeyColor::Blue => Color::BLUE,
+        }
+    }
+
+    // Nombre en la cabecera del nivel (`exit = gold, red`)
+    pub fn name(self) -> &'static str {
+        match self {
+            KeyColor::Gold => "gold",
+            KeyColor::Red => "red",
+            KeyColor::Blue => "blue",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        KeyColor::ALL.into_iter().find(|c| c.name() == name)
+    }
+
+    pub fn from_key_glyph(glyph: char) -> Option<Self> {
+        KeyColor::ALL.into_iter().find(|c| c.key_glyph() == glyph)
+    }
+
+    pub fn from_gate_glyph(glyph: char) -> Option<Self> {
+        KeyColor::ALL.into_iter().find(|c| c.gate_glyph() == glyph)
+    }
+}
 
 pub struct Key {
     pub pos: Vector2,
     pub texture_key: char,
+    pub color: KeyColor,
 }
 
 impl Key {
-    pub fn new(x: f32, y: f32, texture_key: char) -> Self {
+    pub fn new(x: f32, y: f32, color: KeyColor) -> Self {
         Key {
             pos: Vector2::new(x, y), 
-            texture_key,
+            texture_key: color.key_glyph(),
+            color,
         }
     }
+
+    // Las llaves descansan en el piso
+    pub fn sprite(&self) -> Sprite {
+        Sprite::new(self.pos, self.texture_key)
+            .with_size(KEY_SPRITE_SIZE)
+            .with_anchor(Anchor::Floor)
+    }
 }
 
+// Tamaños en unidades de mundo (una pared mide 100)
+const KEY_SPRITE_SIZE: f32 = 50.0;
+const BATTERY_SPRITE_SIZE: f32 = 45.0;
+// La batería flota sobre el piso subiendo y bajando
+const BATTERY_BOB_HEIGHT: f32 = 8.0;
+const BATTERY_BOB_SPEED: f32 = 3.0; // Radianes por segundo
+
 // Nueva estructura para la batería
 pub struct Battery {
     pub pos: Vector2,
@@ -25,6 +98,8 @@ pub struct Battery {
     pub move_timer: f32,         // Tiempo acumulado para moverse
     pub move_duration: f32,      // Intervalo entre movimientos
     pub target_pos: Vector2,     // Posición objetivo para el movimiento
+    pub roam_goal: Option<(usize, usize)>, // Celda lejana hacia la que deambula (por A*)
+    pub bob_time: f32,           // Tiempo acumulado para el balanceo vertical
 }
 
 impl Battery {
@@ -38,7 +113,18 @@ impl Battery {
             move_timer: 0.0,
             move_duration: 2.0, // Se mueve cada 2 segundos
             target_pos: Vector2::new(x, y), // Inicialmente se mueve a su propia posición
+            roam_goal: None,
+            bob_time: 0.0,
         }
     }
-}
 
+    // Sprite con la textura del frame actual de la animación
+    pub fn sprite(&self) -> Sprite {
+        // Entre 0 y el doble de la amplitud: nunca atraviesa el piso
+        let bob = BATTERY_BOB_HEIGHT * (1.0 + (self.bob_time * BATTERY_BOB_SPEED).sin());
+        Sprite::new(self.pos, self.texture_keys[self.current_frame])
+            .with_size(BATTERY_SPRITE_SIZE)
+            .with_anchor(Anchor::Floor)
+            .with_offset(bob)
+    }
+}
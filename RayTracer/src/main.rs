@@ -10,127 +10,119 @@ mod textures;
 mod key;
 mod text;
 mod audio;
+mod headless;
+mod sprite;
+mod generator;
+mod navigation;
+mod door;
+mod inventory;
+mod input;
+mod settings;
+mod clock;
 
 use raylib::prelude::*;
 use std::thread;
 use rand::prelude::*;
 use std::time::{Duration, Instant};
 use player::{Player, process_events};
-use framebuffer::Framebuffer;
-use maze::{Maze,load_maze};
-use caster::{cast_ray, Intersect};
+use framebuffer::{ColumnBand, Framebuffer};
+use maze::{EntityKind, Level, DOOR, LevelInfo, Maze, Surfaces, SKYLIGHT, DEFAULT_TIME_LIMIT, discover_levels, load_maze, parse_level};
+use generator::{Algorithm, GeneratedMaze, GeneratorOptions};
+use caster::{cast_ray, trace_ray, Intersect, WallShading, TEXTURE_SIZE};
 use std::f32::consts::PI;
 use textures::TextureManager;
-use key::{Key, Battery};
+use key::{Key, KeyColor, Battery};
+use door::Doors;
+use inventory::{Inventory, Item};
+use input::{capture_binding, Action, ControlsError, InputMap};
+use sprite::{Sprite, draw_sprite, draw_sprites};
 use text::Font;
 use audio::AudioPlayer;
-
-const TRANSPARENT_COLOR: Color = Color::new(0, 0, 0, 0);
-const MAX_LIFE: f32 = 120.0; // 60 segundos de vida máxima
+use clock::GameClock;
+use settings::{clamp_window_size, settings_path, SettingField, Settings, SettingsError};
+
+const BLOCK_SIZE: usize = 100;
+// Carpeta donde el menú busca niveles
+const LEVELS_DIR: &str = "levels";
+// Controles reasignados (se crea al salir de la pantalla de controles)
+const CONTROLS_FILE: &str = "controls.txt";
+// Distancia (en celdas de camino) a la que las baterías huyen del jugador
+const BATTERY_FLEE_DISTANCE: u32 = 4;
+// Niveles visibles a la vez en el menú (el resto se alcanza desplazando)
+const MENU_VISIBLE_LEVELS: usize = 5;
+
+// Segundos que suma cada batería recogida
+const BATTERY_TIME_BONUS: f32 = 30.0;
 
 struct GameState {
-    life: f32,
-    game_start_time: Instant,
-    has_key: bool,
+    clock: GameClock, // Tiempo restante del nivel actual
+    par_time: Option<f32>, // Tiempo de referencia del camino más corto
+    inventory: Inventory,
+    exit_keys: Vec<KeyColor>, // Llaves que pide la salida del nivel actual
     flashlight_on: bool,
 }
 
 impl GameState {
     fn new() -> Self {
         GameState {
-            life: MAX_LIFE,
-            game_start_time: Instant::now(),
-            has_key: false,
+            clock: GameClock::new(DEFAULT_TIME_LIMIT),
+            par_time: None,
+            inventory: Inventory::default(),
+            exit_keys: vec![KeyColor::Gold],
             flashlight_on: false,
         }
     }
 
-    fn update_life(&mut self) {
-        let elapsed = self.game_start_time.elapsed().as_secs_f32();
-        self.life = (MAX_LIFE - elapsed).max(0.0);
+    // Avanza el reloj con el dt del cuadro y devuelve el dt de juego
+    // (el que mueve al jugador y al mundo: 0 en pausa, menor en cámara lenta)
+    fn update_clock(&mut self, dt: f32) -> f32 {
+        self.clock.tick(dt)
+    }
+
+    fn life(&self) -> f32 {
+        self.clock.remaining()
+    }
+
+    fn max_life(&self) -> f32 {
+        self.clock.limit()
     }
 
     fn is_alive(&self) -> bool {
-        self.life > 0.0
+        !self.clock.is_expired()
     }
 
-    fn collect_key(&mut self) {
-        self.has_key = true;
+    // Se tienen todas las llaves que pide la salida
+    fn can_exit(&self) -> bool {
+        self.exit_keys.iter().all(|&color| self.inventory.has_key(color))
     }
 
     fn add_time(&mut self, time: f32) {
-        self.life = (self.life + time).min(MAX_LIFE); // No exceder el tiempo máximo
+        self.clock.add_bonus(time); // No excede el tiempo máximo
+    }
+
+    fn remove_time(&mut self, time: f32) {
+        self.clock.apply_penalty(time);
     }
 
-    fn reset(&mut self) {
-        self.life = MAX_LIFE;
-        self.game_start_time = Instant::now();
-        self.has_key = false;
+    fn reset(&mut self, level: &Level) {
+        self.clock = GameClock::new(level.time_limit);
+        self.exit_keys = level.exit_keys.clone();
+        self.inventory.clear();
         self.flashlight_on = false;
     }
 }
 
-#[derive(PartialEq)]
+#[derive(Clone, Copy, PartialEq)]
 enum ScreenState {
     MainMenu,
+    Controls,
+    Options,
     Playing,
+    Paused,
     Win,
     Lose,
 }
 
-fn draw_sprite(
-    framebuffer: &mut Framebuffer,
-    player: &Player,
-    key: &Key,
-    texture_manager: &TextureManager
-) {
-    let sprite_a = (key.pos.y - player.pos.y).atan2(key.pos.x - player.pos.x);
-    let mut angle_diff = sprite_a - player.a;
-    while angle_diff > PI {
-        angle_diff -= 2.0 * PI;
-    }
-    while angle_diff < -PI {
-        angle_diff += 2.0 * PI;
-    }
-
-    if angle_diff.abs() > player.fov / 2.0 {
-        return;
-    }
-
-    let sprite_d = ((player.pos.x - key.pos.x).powi(2) + (player.pos.y - key.pos.y).powi(2)).sqrt();
-
-    // near plane           far plane
-    if sprite_d < 50.0 || sprite_d > 1000.0 {
-        return;
-    }
-
-    let screen_height = framebuffer.height as f32;
-    let screen_width = framebuffer.width as f32;
-
-    let sprite_size = (screen_height / sprite_d) * 70.0;
-    let screen_x = ((angle_diff / player.fov) + 0.5) * screen_width;
-
-    let start_x = (screen_x - sprite_size / 2.0).max(0.0) as usize;
-    let start_y = (screen_height / 2.0 - sprite_size / 2.0).max(0.0) as usize;
-    let sprite_size_usize = sprite_size as usize;
-    let end_x = (start_x + sprite_size_usize).min(framebuffer.width as usize);
-    let end_y = (start_y + sprite_size_usize).min(framebuffer.height as usize);
-
-    for x in start_x..end_x {
-        for y in start_y..end_y {
-            let tx = ((x - start_x) * 128 / sprite_size_usize) as u32;
-            let ty = ((y - start_y) * 128 / sprite_size_usize) as u32;
-
-            let color = texture_manager.get_pixel_color(key.texture_key, tx, ty);
-            
-            if color != TRANSPARENT_COLOR {
-                framebuffer.set_current_color(color);
-                framebuffer.set_pixel(x as i32, y as i32);
-            }
-        }
-    }
-}
-
 fn draw_cell(
     framebuffer: &mut Framebuffer,
     xo: usize,
@@ -146,21 +138,26 @@ fn draw_cell(
     let color = match cell {
         'g' => Color::GREEN,    // Meta - verde
         'k' => Color::GOLD,     // Llave - dorado
-        _ => Color::RED,        // Paredes - rojo
+        _ => door_color(cell).unwrap_or(Color::RED), // Puertas, o paredes en rojo
     };
 
     framebuffer.set_current_color(color);
 
-    for x in xo..xo + block_size {
-        for y in yo..yo + block_size {
-            framebuffer.set_pixel(x as i32, y as i32);
-        }
+    framebuffer.fill_rect(xo as i32, yo as i32, block_size as i32, block_size as i32);
+}
+
+// Color de una puerta en los mapas: café la normal, la de su llave si está cerrada
+fn door_color(cell: char) -> Option<Color> {
+    if cell == DOOR {
+        return Some(Color::BROWN);
     }
+    KeyColor::from_gate_glyph(cell).map(KeyColor::color)
 }
 
 pub fn render_maze(
     framebuffer: &mut Framebuffer,
     maze: &Maze,
+    doors: &Doors,
     block_size: usize,
     player: &Player,
 ) {
@@ -182,46 +179,164 @@ pub fn render_maze(
     for i in 0..num_rays {
         let current_ray = i as f32 / num_rays as f32;
         let  a = (player.a - (player.fov / 2.0)) + (player.fov * current_ray);
-        cast_ray(framebuffer, &maze, &player, a, block_size, true);
+        cast_ray(framebuffer, maze, doors, player, a, block_size, true);
     }
 }
 
+// Altura proyectada de las paredes (en unidades de mundo)
+const WALL_SCALE: f32 = 100.0;
+
+// Color de piso/techo en el punto del mundo visto a distancia perpendicular `p`
+// por un rayo con dirección `ray` (ya dividida entre el coseno de la desviación).
+fn surface_color(
+    layer: &[Vec<char>],
+    texture_cache: &TextureManager,
+    player: &Player,
+    ray: Vector2,
+    p: f32,
+    block_size: usize,
+) -> Option<Color> {
+    let block = block_size as f32;
+    let wx = (player.pos.x + ray.x * p) / block;
+    let wy = (player.pos.y + ray.y * p) / block;
+    if wx < 0.0 || wy < 0.0 {
+        return None;
+    }
+    let cell = layer.get(wy as usize).and_then(|row| row.get(wx as usize)).copied()?;
+    texture_cache.get_surface_color(cell, wx.fract(), wy.fract())
+}
+
+// Tope de hilos de render: se lanzan en cada pasada de cada cuadro, y con más
+// bandas cada una queda tan fina que lanzar su hilo cuesta más de lo que ahorra
+const MAX_RENDER_THREADS: usize = 8;
+
+// Hilos de render por defecto: uno por núcleo disponible, hasta el tope
+fn default_render_threads() -> usize {
+    thread::available_parallelism().map_or(1, |n| n.get()).min(MAX_RENDER_THREADS)
+}
+
+// Procesa cada banda en paralelo. La primera se queda en el hilo actual, así
+// con una sola banda no se lanza ningún hilo.
+fn for_each_band<B: Send>(bands: Vec<B>, work: impl Fn(B) + Sync) {
+    let mut bands = bands.into_iter();
+    let Some(first) = bands.next() else {
+        return;
+    };
+    thread::scope(|scope| {
+        let work = &work;
+        for band in bands {
+            scope.spawn(move || work(band));
+        }
+        work(first);
+    });
+}
+
+// Render 3D por columnas, repartido en bandas de columnas entre `threads`
+// hilos. Cada columna se calcula de forma independiente, así que el resultado
+// es idéntico al de un solo hilo. Devuelve el z-buffer de las paredes.
 pub fn render_3d(
     framebuffer: &mut Framebuffer,
     maze: &Maze,
+    doors: &Doors,
+    surfaces: &Surfaces,
+    block_size: usize,
+    player: &Player,
+    texture_cache: &TextureManager,
+    shading: &WallShading,
+    threads: usize,
+) -> Vec<f32> {
+    let width = framebuffer.width;
+    let height = framebuffer.height;
+    // Z-buffer: distancia perpendicular a la pared de cada columna
+    let mut depth = vec![f32::INFINITY; width.max(0) as usize];
+    let mut rest = depth.as_mut_slice();
+    let bands: Vec<_> = framebuffer
+        .column_bands(threads)
+        .into_iter()
+        .map(|band| {
+            let (band_depth, tail) = std::mem::take(&mut rest).split_at_mut(band.columns().len());
+            rest = tail;
+            (band, band_depth)
+        })
+        .collect();
+    for_each_band(bands, |(mut band, band_depth)| {
+        render_columns(&mut band, band_depth, width, height, maze, doors, surfaces, block_size, player, texture_cache, shading);
+    });
+
+    depth
+}
+
+fn render_columns(
+    band: &mut ColumnBand,
+    depth: &mut [f32],
+    num_rays: i32,
+    height: i32,
+    maze: &Maze,
+    doors: &Doors,
+    surfaces: &Surfaces,
     block_size: usize,
     player: &Player,
     texture_cache: &TextureManager,
+    shading: &WallShading,
 ) {
-    let num_rays = framebuffer.width;
-    let hh = framebuffer.height as f32/ 2.0;
+    let hh = height as f32/ 2.0;
 
-    for i in 0..num_rays {
+    for i in band.columns() {
         let current_ray = i as f32 / num_rays as f32;
         let a = (player.a - (player.fov / 2.0)) + (player.fov * current_ray);
         let angle_diff = a - player.a;
-        let intersect = cast_ray(framebuffer, &maze, &player, a, block_size, false);
+        let intersect = trace_ray(maze, doors, player, a, block_size);
         let d = intersect.distance;
         let c = intersect.impact;
         
+        let corrected_distance = (d * angle_diff.cos()).max(1.0);
+        let stake_height = (hh / corrected_distance)*WALL_SCALE;
+        let half_stake_height = stake_height / 2.0;
+        let stake_top = hh - half_stake_height;
+        let stake_bottom = hh + half_stake_height;
+
+        // Solo recorrer la parte visible de la columna
+        let y_start = stake_top.max(0.0) as usize;
+        let y_end = stake_bottom.min(height as f32) as usize;
+
+        // Piso y techo: cada fila corresponde a una distancia perpendicular fija
+        let ray = Vector2::new(a.cos() / angle_diff.cos(), a.sin() / angle_diff.cos());
+        for y in y_end..height as usize {
+            let row = y as f32 + 0.5 - hh;
+            let p = hh * WALL_SCALE * 0.5 / row;
+            if let Some(color) = surface_color(&surfaces.floor, texture_cache, player, ray, p, block_size) {
+                band.set_pixel(i, y as i32, color);
+            }
+        }
+        for y in 0..y_start {
+            let row = hh - (y as f32 + 0.5);
+            let p = hh * WALL_SCALE * 0.5 / row;
+            let wx = (player.pos.x + ray.x * p) / block_size as f32;
+            let wy = (player.pos.y + ray.y * p) / block_size as f32;
+            let color = if wx >= 0.0 && wy >= 0.0 && surfaces.ceiling_at(wx as usize, wy as usize) == SKYLIGHT {
+                // Tragaluz: el cielo se muestrea según la dirección de la vista, no la posición
+                texture_cache.get_surface_color(SKYLIGHT, (a / (2.0 * PI)).rem_euclid(1.0), y as f32 / hh)
+            } else {
+                surface_color(&surfaces.ceiling, texture_cache, player, ray, p, block_size)
+            };
+            if let Some(color) = color {
+                band.set_pixel(i, y as i32, color);
+            }
+        }
+
         // Saltar si el rayo choca con 'g' (lo renderizaremos como sprite)
         if c == 'g' {
             continue;
         }
-        
-        let corrected_distance = d * angle_diff.cos() as f32;
-        let stake_height = (hh / corrected_distance)*100.0;
-        let half_stake_height = stake_height / 2.0;
-        let stake_top = (hh - half_stake_height) as usize;
-        let stake_bottom = (hh + half_stake_height) as usize;
 
-        for y in stake_top..stake_bottom {
-            let tx = intersect.tx;
-            let ty = ((y as f32 - stake_top as f32) / (stake_bottom as f32 - stake_top as f32))*128.0;
-            let color = texture_cache.get_pixel_color(c, tx as u32, ty as u32);
+        depth[(i - band.columns().start) as usize] = corrected_distance;
+        let tx = intersect.tx;
 
-            framebuffer.set_current_color(color);
-            framebuffer.set_pixel(i, y as i32);
+        for y in y_start..y_end {
+            let ty = ((y as f32 - stake_top) / stake_height) * TEXTURE_SIZE;
+            let color = shading.apply(texture_cache.get_pixel_color(c, tx as u32, ty as u32), intersect.side);
+
+            band.set_pixel(i, y as i32, color);
         }
     }
 }
@@ -232,11 +347,11 @@ fn render_key(
     texture_cache: &TextureManager,
 ) {
     let key = vec![
-        Key::new(250.0, 250.0, 'k'),
+        Key::new(250.0, 250.0, KeyColor::Gold),
     ];
 
     for key in key {
-        draw_sprite(framebuffer, &player, &key, texture_cache);
+        draw_sprite(framebuffer, player, &key.sprite(), texture_cache, &[]);
     }
 }
 
@@ -253,11 +368,7 @@ fn render_minimap(
     
     // Fondo semitransparente del minimapa
     framebuffer.set_current_color(Color::new(0, 0, 0, 180));
-    for x in pos_x..pos_x + minimap_size as i32 {
-        for y in pos_y..pos_y + minimap_size as i32 {
-            framebuffer.set_pixel(x, y);
-        }
-    }
+    framebuffer.fill_rect(pos_x, pos_y, minimap_size as i32, minimap_size as i32);
     
     // Borde del minimapa
     framebuffer.set_current_color(Color::WHITE);
@@ -280,14 +391,10 @@ fn render_minimap(
                 ' ' => framebuffer.set_current_color(Color::DARKGRAY),
                 'g' => framebuffer.set_current_color(Color::GREEN), // Meta
                 'k' => framebuffer.set_current_color(Color::GOLD),  // Llave
-                _ => framebuffer.set_current_color(Color::RED),     // Paredes
+                _ => framebuffer.set_current_color(door_color(cell).unwrap_or(Color::RED)), // Puertas y paredes
             }
             
-            for x in xo..xo + minimap_block_size as i32 {
-                for y in yo..yo + minimap_block_size as i32 {
-                    framebuffer.set_pixel(x, y);
-                }
-            }
+            framebuffer.fill_rect(xo, yo, minimap_block_size as i32, minimap_block_size as i32);
         }
     }
     
@@ -309,7 +416,67 @@ fn render_minimap(
     framebuffer.draw_line(player_minimap_x, player_minimap_y, direction_x, direction_y);
 }
 
-fn draw_life_bar(framebuffer: &mut Framebuffer, game_state: &GameState, font: &Font) {
+// Ícono del HUD a partir de la textura de un objeto; atenuado si aún no se tiene
+fn draw_icon(framebuffer: &mut Framebuffer, texture_cache: &TextureManager, texture: char, x: i32, y: i32, size: i32, dim: bool) {
+    let Some((width, height)) = texture_cache.image_size(texture) else {
+        return;
+    };
+    for dy in 0..size {
+        for dx in 0..size {
+            let tx = (dx * width / size) as u32;
+            let ty = (dy * height / size) as u32;
+            let mut color = texture_cache.get_pixel_color(texture, tx, ty);
+            if color.a == 0 {
+                continue;
+            }
+            if dim {
+                color = Color::new(color.r / 4, color.g / 4, color.b / 4, color.a);
+            }
+            framebuffer.set_pixel_color(x + dx, y + dy, color);
+        }
+    }
+}
+
+// Inventario en el HUD: primero las llaves que pide la salida (atenuadas si
+// faltan), después el resto de objetos con su cantidad
+fn draw_inventory(framebuffer: &mut Framebuffer, game_state: &GameState, texture_cache: &TextureManager, font: &Font, x: i32, y: i32) {
+    const ICON_SIZE: i32 = 20;
+    const ICON_GAP: i32 = 4;
+    let mut icon_x = x;
+
+    for &color in &game_state.exit_keys {
+        let held = game_state.inventory.has_key(color);
+        draw_icon(framebuffer, texture_cache, color.key_glyph(), icon_x, y, ICON_SIZE, !held);
+        icon_x += ICON_SIZE + ICON_GAP;
+    }
+    for (item, count) in game_state.inventory.items() {
+        if matches!(item, Item::Key(color) if game_state.exit_keys.contains(&color)) {
+            continue;
+        }
+        draw_icon(framebuffer, texture_cache, item.icon(), icon_x, y, ICON_SIZE, false);
+        icon_x += ICON_SIZE;
+        if count > 1 {
+            let label = format!("X{}", count);
+            font.draw_text(framebuffer, &label, icon_x, y + ICON_SIZE - 8, 1, Color::WHITE);
+            icon_x += 6 * label.len() as i32;
+        }
+        icon_x += ICON_GAP;
+    }
+}
+
+// Barra de resistencia para correr; roja mientras está agotada
+fn draw_stamina_bar(framebuffer: &mut Framebuffer, player: &Player, x: i32, y: i32, width: i32) {
+    let height = 6;
+    framebuffer.set_current_color(Color::DARKGRAY);
+    framebuffer.fill_rect(x, y, width, height);
+    let fill = (width as f32 * player.stamina.clamp(0.0, 1.0)) as i32;
+    if fill > 0 {
+        framebuffer.set_current_color(if player.exhausted { Color::RED } else { Color::SKYBLUE });
+        framebuffer.fill_rect(x, y, fill, height);
+    }
+}
+
+fn draw_life_bar(framebuffer: &mut Framebuffer, game_state: &GameState, player: &Player, texture_cache: &TextureManager, font: &Font) {
     let bar_width = 200;
     let bar_height = 20;
     let x = 20;
@@ -317,29 +484,21 @@ fn draw_life_bar(framebuffer: &mut Framebuffer, game_state: &GameState, font: &F
     
     // Fondo de la barra de vida
     framebuffer.set_current_color(Color::DARKGRAY);
-    for dx in 0..bar_width {
-        for dy in 0..bar_height {
-            framebuffer.set_pixel(x + dx, y + dy);
-        }
-    }
+    framebuffer.fill_rect(x, y, bar_width, bar_height);
     
     // Vida actual
-    let life_width = (bar_width as f32 * (game_state.life / MAX_LIFE)) as i32;
+    let life_width = (bar_width as f32 * (game_state.life() / game_state.max_life())) as i32;
     if life_width > 0 {
-        let life_color = if game_state.life > MAX_LIFE * 0.5 {
+        let life_color = if game_state.life() > game_state.max_life() * 0.5 {
             Color::GREEN
-        } else if game_state.life > MAX_LIFE * 0.25 {
+        } else if game_state.life() > game_state.max_life() * 0.25 {
             Color::YELLOW
         } else {
             Color::RED
         };
         
         framebuffer.set_current_color(life_color);
-        for dx in 0..life_width {
-            for dy in 0..bar_height {
-                framebuffer.set_pixel(x + dx, y + dy);
-            }
-        }
+        framebuffer.fill_rect(x, y, life_width, bar_height);
     }
     
     // Borde de la barra
@@ -354,15 +513,11 @@ fn draw_life_bar(framebuffer: &mut Framebuffer, game_state: &GameState, font: &F
     }
     
     // Texto de la vida - CORRECCIÓN: quitar &mut
-    let life_text = format!("TIEMPO: {:.1}s", game_state.life);
+    let life_text = format!("TIEMPO: {:.1}s", game_state.life());
     font.draw_text(framebuffer, &life_text, x, y + bar_height + 5, 1, Color::WHITE);
     
-    // Indicador de llave - CORRECCIÓN: quitar &mut
-    if game_state.has_key {
-        font.draw_text(framebuffer, "LLAVE: ✓", x, y + bar_height + 20, 1, Color::GOLD);
-    } else {
-        font.draw_text(framebuffer, "LLAVE: ✗", x, y + bar_height + 20, 1, Color::GRAY);
-    }
+    draw_stamina_bar(framebuffer, player, x, y + bar_height + 18, bar_width);
+    draw_inventory(framebuffer, game_state, texture_cache, font, x, y + bar_height + 30);
 }
 
 fn draw_main_menu(framebuffer: &mut Framebuffer, selected_level: i32) {
@@ -371,11 +526,7 @@ fn draw_main_menu(framebuffer: &mut Framebuffer, selected_level: i32) {
     
     // Fondo simple
     framebuffer.set_current_color(Color::BLACK);
-    for y in 0..height {
-        for x in 0..width {
-            framebuffer.set_pixel(x, y);
-        }
-    }
+    framebuffer.fill_rect(0, 0, width, height);
     
     // Título - líneas de píxeles
     framebuffer.set_current_color(Color::RED);
@@ -452,20 +603,23 @@ fn draw_simple_text(framebuffer: &mut Framebuffer, text: &str, x: i32, y: i32, s
     }
 }
 
-fn check_key_collision(player: &Player, keys: &[Key], game_state: &mut GameState, block_size: usize) -> bool {
+// Recoge las llaves de la celda del jugador (desaparecen del nivel)
+fn check_key_collision(player: &Player, keys: &mut Vec<Key>, game_state: &mut GameState, block_size: usize) -> bool {
     let player_grid_x = (player.pos.x / block_size as f32) as usize;
     let player_grid_y = (player.pos.y / block_size as f32) as usize;
-    
-    for key in keys {
+    let count = keys.len();
+
+    keys.retain(|key| {
         let key_grid_x = (key.pos.x / block_size as f32) as usize;
         let key_grid_y = (key.pos.y / block_size as f32) as usize;
-        
-        if player_grid_x == key_grid_x && player_grid_y == key_grid_y && !game_state.has_key {
-            game_state.collect_key();
-            return true;
+
+        if player_grid_x == key_grid_x && player_grid_y == key_grid_y {
+            game_state.inventory.add(Item::Key(key.color));
+            return false;
         }
-    }
-    false
+        true
+    });
+    keys.len() != count
 }
 
 fn check_goal_collision(player: &Player, maze: &Maze, game_state: &GameState, block_size: usize) -> bool {
@@ -474,31 +628,147 @@ fn check_goal_collision(player: &Player, maze: &Maze, game_state: &GameState, bl
     
     if player_grid_y < maze.len() && player_grid_x < maze[player_grid_y].len() {
         let cell = maze[player_grid_y][player_grid_x];
-        if cell == 'g' && game_state.has_key {
+        if cell == 'g' && game_state.can_exit() {
             return true;
         }
     }
     false
 }
 
+// Entradas de una acción para mostrar en pantalla (la fuente no tiene '_')
+fn binding_label(input: &InputMap, action: Action) -> String {
+    let names: Vec<String> = input.bindings(action).iter().map(|b| b.name().replace('_', " ")).collect();
+    if names.is_empty() { "NINGUNA".to_string() } else { names.join(", ") }
+}
+
+fn draw_controls_screen(
+    framebuffer: &mut Framebuffer,
+    font: &Font,
+    input: &InputMap,
+    actions: &[Action],
+    selected: usize,
+    capturing: bool,
+) {
+    let width = framebuffer.width;
+    framebuffer.set_current_color(Color::new(20, 20, 40, 255));
+    framebuffer.fill_rect(0, 0, width, framebuffer.height);
+
+    font.draw_text(framebuffer, "CONTROLES", width / 2 - 45, 40, 2, Color::YELLOW);
+    for (row, &action) in actions.iter().enumerate() {
+        let y = 90 + row as i32 * 22;
+        let (marker, color) = if row == selected { (">", Color::GREEN) } else { (" ", Color::LIGHTGRAY) };
+        let value = if row == selected && capturing {
+            "PRESIONA UNA TECLA O BOTON (ESC CANCELA)".to_string()
+        } else {
+            binding_label(input, action)
+        };
+        font.draw_text(framebuffer, &format!("{} {}", marker, action.label()), width / 2 - 250, y, 1, color);
+        font.draw_text(framebuffer, &value, width / 2 + 20, y, 1, color);
+    }
+
+    let footer = format!(
+        "{}: CAMBIAR   R: RESTAURAR   {}: GUARDAR Y VOLVER",
+        binding_label(input, Action::Confirm),
+        binding_label(input, Action::Back)
+    );
+    font.draw_text(framebuffer, &footer, width / 2 - 250, 90 + actions.len() as i32 * 22 + 20, 1, Color::SKYBLUE);
+}
+
+fn draw_options_screen(framebuffer: &mut Framebuffer, font: &Font, input: &InputMap, settings: &Settings, selected: usize) {
+    let width = framebuffer.width;
+    framebuffer.set_current_color(Color::new(20, 20, 40, 255));
+    framebuffer.fill_rect(0, 0, width, framebuffer.height);
+
+    font.draw_text(framebuffer, "OPCIONES", width / 2 - 40, 40, 2, Color::YELLOW);
+    for (row, &field) in SettingField::ALL.iter().enumerate() {
+        let y = 110 + row as i32 * 30;
+        let (marker, color) = if row == selected { (">", Color::GREEN) } else { (" ", Color::LIGHTGRAY) };
+        font.draw_text(framebuffer, &format!("{} {}", marker, field.label()), width / 2 - 250, y, 1, color);
+        font.draw_text(framebuffer, &format!("< {} >", field.value_text(settings)), width / 2 + 60, y, 1, color);
+    }
+
+    let footer = format!(
+        "{} / {}: CAMBIAR   {}: GUARDAR Y VOLVER",
+        binding_label(input, Action::MenuLeft),
+        binding_label(input, Action::MenuRight),
+        binding_label(input, Action::Back)
+    );
+    font.draw_text(framebuffer, &footer, width / 2 - 250, 110 + SettingField::ALL.len() as i32 * 30 + 20, 1, Color::SKYBLUE);
+}
+
+// Entradas del menú de pausa
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum PauseItem {
+    Resume,
+    Restart,
+    Options,
+    Quit,
+}
+
+impl PauseItem {
+    const ALL: [PauseItem; 4] = [PauseItem::Resume, PauseItem::Restart, PauseItem::Options, PauseItem::Quit];
+
+    fn label(self) -> &'static str {
+        match self {
+            PauseItem::Resume => "CONTINUAR",
+            PauseItem::Restart => "REINICIAR NIVEL",
+            PauseItem::Options => "OPCIONES",
+            PauseItem::Quit => "SALIR AL MENU",
+        }
+    }
+}
+
+// Oscurece el cuadro ya dibujado para poner un menú encima
+fn dim_frame(framebuffer: &mut Framebuffer) {
+    for y in 0..framebuffer.height {
+        for x in 0..framebuffer.width {
+            if let Some(color) = framebuffer.get_pixel_color(x, y) {
+                framebuffer.set_pixel_color(x, y, Color::new(color.r / 3, color.g / 3, color.b / 3, color.a));
+            }
+        }
+    }
+}
+
+fn draw_pause_menu(framebuffer: &mut Framebuffer, font: &Font, input: &InputMap, selected: usize) {
+    let width = framebuffer.width;
+    let top = framebuffer.height / 2 - 90;
+    font.draw_text(framebuffer, "PAUSA", width / 2 - 25, top, 2, Color::YELLOW);
+    for (row, item) in PauseItem::ALL.iter().enumerate() {
+        let y = top + 50 + row as i32 * 25;
+        if row == selected {
+            font.draw_text(framebuffer, &format!("> {} <", item.label()), width / 2 - 70, y, 1, Color::GREEN);
+        } else {
+            font.draw_text(framebuffer, &format!("  {}  ", item.label()), width / 2 - 70, y, 1, Color::LIGHTGRAY);
+        }
+    }
+    let footer = format!("{}: CONTINUAR", binding_label(input, Action::Pause));
+    font.draw_text(framebuffer, &footer, width / 2 - 70, top + 50 + PauseItem::ALL.len() as i32 * 25 + 15, 1, Color::SKYBLUE);
+}
+
+// Aplica las opciones que se pueden cambiar sin recrear nada
+fn apply_settings(settings: &Settings, player: &mut Player, audio_player: &AudioPlayer) {
+    player.fov = settings.fov();
+    player.mouse_sensitivity = settings.mouse_sensitivity;
+    audio_player.set_volume(settings.music_volume);
+}
+
 fn draw_win_screen(framebuffer: &mut Framebuffer, font: &Font, game_state: &GameState) {
     let width = framebuffer.width;
     let height = framebuffer.height;
     
     // Fondo verde de victoria
     framebuffer.set_current_color(Color::new(0, 100, 0, 255));
-    for y in 0..height {
-        for x in 0..width {
-            framebuffer.set_pixel(x, y);
-        }
-    }
+    framebuffer.fill_rect(0, 0, width, height);
     
     // Mensaje de victoria - CORRECCIÓN: quitar &mut en todas las llamadas
     font.draw_text(framebuffer, "¡VICTORIA!", width / 2 - 60, height / 2 - 50, 3, Color::GOLD);
     font.draw_text(framebuffer, "Encontraste la llave y escapaste!", width / 2 - 120, height / 2, 1, Color::WHITE);
     
-    let time_used = MAX_LIFE - game_state.life;
+    let time_used = game_state.clock.elapsed();
     font.draw_text(framebuffer, &format!("Tiempo: {:.1} segundos", time_used), width / 2 - 80, height / 2 + 30, 1, Color::YELLOW);
+    if let Some(par) = game_state.par_time {
+        font.draw_text(framebuffer, &format!("PAR: {:.1} segundos", par), width / 2 - 80, height / 2 + 50, 1, Color::SKYBLUE);
+    }
     
     font.draw_text(framebuffer, "Presiona ESPACIO para jugar otra vez", width / 2 - 140, height / 2 + 80, 1, Color::LIGHTGRAY);
     font.draw_text(framebuffer, "Presiona ESC para salir al menu", width / 2 - 120, height / 2 + 110, 1, Color::LIGHTGRAY);
@@ -510,11 +780,7 @@ fn draw_lose_screen(framebuffer: &mut Framebuffer, font: &Font) {
     
     // Fondo rojo de derrota
     framebuffer.set_current_color(Color::new(100, 0, 0, 255));
-    for y in 0..height {
-        for x in 0..width {
-            framebuffer.set_pixel(x, y);
-        }
-    }
+    framebuffer.fill_rect(0, 0, width, height);
     
     // Mensaje de derrota - CORRECCIÓN: quitar &mut en todas las llamadas
     font.draw_text(framebuffer, "¡GAME OVER!", width / 2 - 60, height / 2 - 50, 3, Color::RED);
@@ -525,19 +791,27 @@ fn draw_lose_screen(framebuffer: &mut Framebuffer, font: &Font) {
     font.draw_text(framebuffer, "Presiona ESC para salir al menu", width / 2 - 120, height / 2 + 110, 1, Color::LIGHTGRAY);
 }
 
-fn get_keys() -> Vec<Key> {
-    vec![
-        Key::new(250.0, 250.0, 'k'),
-    ]
+// Centro en coordenadas de mundo de una celda (columna, fila)
+fn cell_center((col, row): (usize, usize), block_size: usize) -> Vector2 {
+    Vector2::new(
+        (col * block_size + block_size / 2) as f32,
+        (row * block_size + block_size / 2) as f32,
+    )
 }
 
-fn draw_goal_sprite(
-    framebuffer: &mut Framebuffer,
-    player: &Player,
-    maze: &Maze,
-    texture_manager: &TextureManager,
-    block_size: usize,
-) {
+fn get_keys(level: &Level, block_size: usize) -> Vec<Key> {
+    level
+        .keys()
+        .map(|(color, cell)| {
+            let pos = cell_center(cell, block_size);
+            Key::new(pos.x, pos.y, color)
+        })
+        .collect()
+}
+
+// Sprites de la meta: uno por cada celda 'g', con textura 'g'
+fn goal_sprites(maze: &Maze, block_size: usize) -> Vec<Sprite> {
+    let mut goals = Vec::new();
     // Buscar la posición de la meta 'g' en el laberinto
     for (j, row) in maze.iter().enumerate() {
         for (i, &cell) in row.iter().enumerate() {
@@ -546,23 +820,29 @@ fn draw_goal_sprite(
                     (i * block_size + block_size / 2) as f32,
                     (j * block_size + block_size / 2) as f32
                 );
-                
-                // Crear un "sprite" temporal para la meta
-                let goal_sprite = Key {
-                    pos: goal_pos,
-                    texture_key: 'g', // Usar 'g' como identificador de textura
-                };
-                
-                // Usar la misma función que para dibujar la llave
-                draw_sprite(framebuffer, player, &goal_sprite, texture_manager);
+                goals.push(Sprite::new(goal_pos, 'g'));
             }
         }
     }
+    goals
+}
+
+// Llaves (si no han sido recolectadas), meta y baterías del cuadro actual
+fn collect_sprites(
+    maze: &Maze,
+    keys: &[Key],
+    batteries: &[Battery],
+    block_size: usize,
+) -> Vec<Sprite> {
+    let mut sprites: Vec<Sprite> = keys.iter().map(Key::sprite).collect();
+    sprites.extend(goal_sprites(maze, block_size));
+    sprites.extend(batteries.iter().map(Battery::sprite));
+    sprites
 }
 
 // --- NUEVAS FUNCIONES PARA EL EFECTO LINTERNA---
 // Aplica un efecto de linterna más realista: gradiente radial
-fn apply_flashlight_effect(framebuffer: &mut Framebuffer, window_width: i32, window_height: i32) {
+fn apply_flashlight_effect(framebuffer: &mut Framebuffer, window_width: i32, window_height: i32, threads: usize) {
     let center_x = (window_width / 2) as f32;
     let center_y = (window_height / 2) as f32;
     // Definir el radio máximo de la linterna (ajusta este valor)
@@ -570,77 +850,81 @@ fn apply_flashlight_effect(framebuffer: &mut Framebuffer, window_width: i32, win
     // Definir el radio donde la luz comienza a atenuarse fuertemente (ángulo cónico)
     let inner_radius = max_radius * 0.3; // 30% del radio máximo
 
-    for y in 0..window_height {
-        for x in 0..window_width {
-            let dx = x as f32 - center_x;
-            let dy = y as f32 - center_y;
-            let distance = (dx.powi(2) + dy.powi(2)).sqrt();
-
-            // Calcular intensidad basada en la distancia
-            let intensity = if distance <= inner_radius {
-                1.0 // Área central completamente iluminada
-            } else if distance <= max_radius {
-                // Gradiente suave entre inner_radius y max_radius
-                // Usamos una curva cuadrática para una caída más natural
-                let t = (distance - inner_radius) / (max_radius - inner_radius);
-                (1.0 - t).powi(2).max(0.0) // Asegura que no sea negativo
-            } else {
-                0.0 // Fuera del radio, completamente oscuro
-            };
+    // Cada hilo procesa su propia banda de filas
+    for_each_band(framebuffer.row_bands(threads), |mut band| {
+        for y in band.rows() {
+            for x in 0..window_width {
+                let dx = x as f32 - center_x;
+                let dy = y as f32 - center_y;
+                let distance = (dx.powi(2) + dy.powi(2)).sqrt();
+
+                // Calcular intensidad basada en la distancia
+                let intensity = if distance <= inner_radius {
+                    1.0 // Área central completamente iluminada
+                } else if distance <= max_radius {
+                    // Gradiente suave entre inner_radius y max_radius
+                    // Usamos una curva cuadrática para una caída más natural
+                    let t = (distance - inner_radius) / (max_radius - inner_radius);
+                    (1.0 - t).powi(2).max(0.0) // Asegura que no sea negativo
+                } else {
+                    0.0 // Fuera del radio, completamente oscuro
+                };
 
-            // Obtener el color actual del píxel
-            if let Some(current_color) = framebuffer.get_pixel_color(x, y) {
-                // Calcular el factor de oscuridad (opuesto a la intensidad)
-                let darkness_factor = 1.0 - intensity;
+                // Obtener el color actual del píxel
+                if let Some(current_color) = band.get_pixel_color(x, y) {
+                    // Calcular el factor de oscuridad (opuesto a la intensidad)
+                    let darkness_factor = 1.0 - intensity;
 
-                // Definir el color base de la oscuridad (negro)
-                let dark_r = 0.0;
-                let dark_g = 0.0;
-                let dark_b = 0.0;
+                    // Definir el color base de la oscuridad (negro)
+                    let dark_r = 0.0;
+                    let dark_g = 0.0;
+                    let dark_b = 0.0;
 
-                // Mezclar el color actual con el negro basado en el factor de oscuridad
-                // Usamos una interpolación lineal ponderada
-                let r = (current_color.r as f32 * intensity + dark_r * darkness_factor) as u8;
-                let g = (current_color.g as f32 * intensity + dark_g * darkness_factor) as u8;
-                let b = (current_color.b as f32 * intensity + dark_b * darkness_factor) as u8;
-                let a = current_color.a; // Mantener la transparencia original
+                    // Mezclar el color actual con el negro basado en el factor de oscuridad
+                    // Usamos una interpolación lineal ponderada
+                    let r = (current_color.r as f32 * intensity + dark_r * darkness_factor) as u8;
+                    let g = (current_color.g as f32 * intensity + dark_g * darkness_factor) as u8;
+                    let b = (current_color.b as f32 * intensity + dark_b * darkness_factor) as u8;
+                    let a = current_color.a; // Mantener la transparencia original
 
-                framebuffer.set_current_color(Color::new(r, g, b, a));
-                framebuffer.set_pixel(x, y);
+                    band.set_pixel(x, y, Color::new(r, g, b, a));
+                }
             }
         }
-    }
+    });
 }
 
 // Opcional: Aplica una oscuridad general cuando la linterna está apagada
-fn apply_general_darkness(framebuffer: &mut Framebuffer, window_width: i32, window_height: i32) {
+fn apply_general_darkness(framebuffer: &mut Framebuffer, window_width: i32, threads: usize) {
     // Definir el color base de la oscuridad (negro con cierta transparencia)
     let darkness_color = Color::new(0, 0, 0, 200); // Negro semi-transparente
 
-    for y in 0..window_height {
-        for x in 0..window_width {
-            // Obtener el color actual del píxel
-            if let Some(current_color) = framebuffer.get_pixel_color(x, y) {
-                // Mezclar el color actual con el color de oscuridad
-                // Usamos una fórmula simple de mezcla (puedes probar otras)
-                let r = ((current_color.r as u16 + darkness_color.r as u16) / 2) as u8;
-                let g = ((current_color.g as u16 + darkness_color.g as u16) / 2) as u8;
-                let b = ((current_color.b as u16 + darkness_color.b as u16) / 2) as u8;
-                let a = current_color.a; // Mantener la transparencia original
-                framebuffer.set_current_color(Color::new(r, g, b, a));
-                framebuffer.set_pixel(x, y);
+    // Cada hilo procesa su propia banda de filas
+    for_each_band(framebuffer.row_bands(threads), |mut band| {
+        for y in band.rows() {
+            for x in 0..window_width {
+                // Obtener el color actual del píxel
+                if let Some(current_color) = band.get_pixel_color(x, y) {
+                    // Mezclar el color actual con el color de oscuridad
+                    // Usamos una fórmula simple de mezcla (puedes probar otras)
+                    let r = ((current_color.r as u16 + darkness_color.r as u16) / 2) as u8;
+                    let g = ((current_color.g as u16 + darkness_color.g as u16) / 2) as u8;
+                    let b = ((current_color.b as u16 + darkness_color.b as u16) / 2) as u8;
+                    let a = current_color.a; // Mantener la transparencia original
+                    band.set_pixel(x, y, Color::new(r, g, b, a));
+                }
             }
         }
-    }
+    });
 }
 
+
 // --- FUNCIONES PARA BATERÍAS ---
-fn initialize_batteries(maze: &Maze, block_size: usize) -> Vec<Battery> {
+fn initialize_batteries(maze: &Maze, count: usize, block_size: usize, rng: &mut impl Rng) -> Vec<Battery> {
     let mut batteries = Vec::new();
-    let mut rng = thread_rng();
 
     // Buscar celdas vacías para posicionar las baterías
-    for _ in 0..2 { // Ejemplo: 2 baterías
+    for _ in 0..count {
         let mut placed = false;
         let mut attempts = 0;
         while !placed && attempts < 100 { // Evitar bucles infinitos
@@ -659,10 +943,41 @@ fn initialize_batteries(maze: &Maze, block_size: usize) -> Vec<Battery> {
     batteries
 }
 
-fn update_batteries(batteries: &mut Vec<Battery>, maze: &Maze, block_size: usize, dt: f32) {
+// Baterías del nivel: las colocadas con 'b' o, si no hay, `level.batteries` al azar
+fn level_batteries(level: &Level, block_size: usize, rng: &mut impl Rng) -> Vec<Battery> {
+    let placed: Vec<Battery> = level
+        .cells_of(EntityKind::Battery)
+        .map(|cell| {
+            let pos = cell_center(cell, block_size);
+            Battery::new(pos.x, pos.y, ['b', 'c', 'd'])
+        })
+        .collect();
+    if placed.is_empty() {
+        initialize_batteries(&level.maze, level.batteries, block_size, rng)
+    } else {
+        placed
+    }
+}
+
+// Celdas de pasillo (' '), las únicas que ocupan las baterías
+fn corridor_cells(maze: &Maze) -> Vec<navigation::Cell> {
+    maze.iter()
+        .enumerate()
+        .flat_map(|(y, row)| row.iter().enumerate().filter(|&(_, &c)| c == ' ').map(move |(x, _)| (x, y)))
+        .collect()
+}
+
+fn update_batteries(batteries: &mut Vec<Battery>, maze: &Maze, player: &Player, block_size: usize, dt: f32) {
     let mut rng = thread_rng();
+    let player_cell = (
+        (player.pos.x / block_size as f32) as usize,
+        (player.pos.y / block_size as f32) as usize,
+    );
+    // Campo de distancias al jugador; solo se calcula si alguna batería decide moverse
+    let mut player_field: Option<navigation::DistanceField> = None;
     for battery in batteries {
         // Actualizar animación
+        battery.bob_time += dt;
         battery.frame_timer += dt;
         if battery.frame_timer >= battery.frame_duration {
             battery.frame_timer -= battery.frame_duration;
@@ -673,27 +988,44 @@ fn update_batteries(batteries: &mut Vec<Battery>, maze: &Maze, block_size: usize
         battery.move_timer += dt;
         if battery.move_timer >= battery.move_duration {
             battery.move_timer -= battery.move_duration;
-            // Buscar nueva posición aleatoria vecina
-            let current_grid_x = (battery.pos.x / block_size as f32) as usize;
-            let current_grid_y = (battery.pos.y / block_size as f32) as usize;
-            let directions = [(0, -1), (1, 0), (0, 1), (-1, 0)]; // Arriba, Derecha, Abajo, Izquierda
-            let valid_moves: Vec<(isize, isize)> = directions.iter()
-                .filter(|&&(dx, dy)| {
-                    let new_x = current_grid_x as isize + dx;
-                    let new_y = current_grid_y as isize + dy;
-                    new_x >= 0 && new_x < maze[0].len() as isize &&
-                    new_y >= 0 && new_y < maze.len() as isize &&
-                    maze[new_y as usize][new_x as usize] == ' '
-                })
-                .cloned()
+            let current = (
+                (battery.pos.x / block_size as f32) as usize,
+                (battery.pos.y / block_size as f32) as usize,
+            );
+            // Las baterías solo ocupan pasillos (' '), nunca la meta
+            let valid_moves: Vec<navigation::Cell> = navigation::neighbors(maze, current)
+                .filter(|&(x, y)| maze[y][x] == ' ')
                 .collect();
 
-            if !valid_moves.is_empty() {
-                let &(dx, dy) = valid_moves.choose(&mut rng).unwrap();
-                battery.target_pos = Vector2::new(
-                    ((current_grid_x as isize + dx) * block_size as isize + block_size as isize / 2) as f32,
-                    ((current_grid_y as isize + dy) * block_size as isize + block_size as isize / 2) as f32,
-                );
+            // Si el jugador está cerca (por camino), huir; si no, deambular
+            // hacia una celda de pasillo elegida al azar, siguiendo el camino de A*
+            let field = player_field.get_or_insert_with(|| navigation::distance_field(maze, &[player_cell]));
+            let next = match field.get(current) {
+                Some(d) if d <= BATTERY_FLEE_DISTANCE => {
+                    battery.roam_goal = None;
+                    valid_moves
+                        .iter()
+                        .copied()
+                        .filter(|&cell| field.get(cell).is_some_and(|n| n > d))
+                        .max_by_key(|&cell| field.get(cell))
+                }
+                _ => {
+                    if battery.roam_goal.is_none_or(|goal| goal == current) {
+                        battery.roam_goal = corridor_cells(maze).choose(&mut rng).copied();
+                    }
+                    let step = battery
+                        .roam_goal
+                        .and_then(|goal| navigation::astar_path(maze, current, goal))
+                        .and_then(|path| path.get(1).copied())
+                        .filter(|cell| valid_moves.contains(cell));
+                    if step.is_none() {
+                        battery.roam_goal = None;
+                    }
+                    step.or_else(|| valid_moves.choose(&mut rng).copied())
+                }
+            };
+            if let Some(cell) = next {
+                battery.target_pos = cell_center(cell, block_size);
             }
             // Si no hay movimientos válidos, se queda quieta, lo cual está bien.
         }
@@ -717,55 +1049,6 @@ fn update_batteries(batteries: &mut Vec<Battery>, maze: &Maze, block_size: usize
     }
 }
 
-
-fn draw_battery(
-    framebuffer: &mut Framebuffer,
-    player: &Player,
-    battery: &Battery,
-    texture_manager: &TextureManager
-) {
-    // Reutilizamos la lógica de draw_sprite, pero usamos la textura del frame actual
-    let sprite_a = (battery.pos.y - player.pos.y).atan2(battery.pos.x - player.pos.x);
-    let mut angle_diff = sprite_a - player.a;
-    while angle_diff > PI {
-        angle_diff -= 2.0 * PI;
-    }
-    while angle_diff < -PI {
-        angle_diff += 2.0 * PI;
-    }
-    if angle_diff.abs() > player.fov / 2.0 {
-        return;
-    }
-    let sprite_d = ((player.pos.x - battery.pos.x).powi(2) + (player.pos.y - battery.pos.y).powi(2)).sqrt();
-    // near plane           far plane
-    if sprite_d < 50.0 || sprite_d > 1000.0 {
-        return;
-    }
-    let screen_height = framebuffer.height as f32;
-    let screen_width = framebuffer.width as f32;
-    let sprite_size = (screen_height / sprite_d) * 70.0; // Tamaño base, ajustable
-    let screen_x = ((angle_diff / player.fov) + 0.5) * screen_width;
-    let start_x = (screen_x - sprite_size / 2.0).max(0.0) as usize;
-    let start_y = (screen_height / 2.0 - sprite_size / 2.0).max(0.0) as usize;
-    let sprite_size_usize = sprite_size as usize;
-    let end_x = (start_x + sprite_size_usize).min(framebuffer.width as usize);
-    let end_y = (start_y + sprite_size_usize).min(framebuffer.height as usize);
-
-    let texture_key = battery.texture_keys[battery.current_frame]; // Obtener la clave de la textura del frame actual
-
-    for x in start_x..end_x {
-        for y in start_y..end_y {
-            let tx = ((x - start_x) * 128 / sprite_size_usize) as u32;
-            let ty = ((y - start_y) * 128 / sprite_size_usize) as u32;
-            let color = texture_manager.get_pixel_color(texture_key, tx, ty);
-            if color != TRANSPARENT_COLOR {
-                framebuffer.set_current_color(color);
-                framebuffer.set_pixel(x as i32, y as i32);
-            }
-        }
-    }
-}
-
 fn check_battery_collision(player: &Player, batteries: &mut Vec<Battery>, game_state: &mut GameState, block_size: usize) -> bool {
     let player_grid_x = (player.pos.x / block_size as f32) as usize;
     let player_grid_y = (player.pos.y / block_size as f32) as usize;
@@ -777,7 +1060,8 @@ fn check_battery_collision(player: &Player, batteries: &mut Vec<Battery>, game_s
 
         if player_grid_x == battery_grid_x && player_grid_y == battery_grid_y {
             // Colisión detectada
-            game_state.add_time(30.0); // Añadir 30 segundos de vida
+            game_state.add_time(BATTERY_TIME_BONUS);
+            game_state.inventory.add(Item::Battery);
             batteries.remove(i); // Eliminar la batería recolectada
             return true; // Indicar que se recolectó una batería
         }
@@ -785,16 +1069,165 @@ fn check_battery_collision(player: &Player, batteries: &mut Vec<Battery>, game_s
     false // No se recolectó ninguna batería
 }
 
+// Escalas de render disponibles (porcentaje de la resolución de la ventana)
+const RENDER_SCALES: [u32; 4] = [25, 50, 75, 100];
+
+// Tamaño interno del raycasting para una escala de render dada
+fn scaled_size(width: i32, height: i32, render_scale: u32) -> (i32, i32) {
+    let scale = render_scale.clamp(RENDER_SCALES[0], 100) as i32;
+    ((width * scale / 100).max(1), (height * scale / 100).max(1))
+}
+
+// Dibuja un cuadro completo de juego: mundo, sprites, iluminación y HUD.
+// El mundo se renderiza en `scene` (resolución interna) y se escala a
+// `framebuffer`; el HUD y el mapa 2D se dibujan siempre a resolución nativa.
+// Lo comparten el bucle principal y el modo headless.
+fn render_frame(
+    framebuffer: &mut Framebuffer,
+    scene: &mut Framebuffer,
+    maze: &Maze,
+    doors: &Doors,
+    surfaces: &Surfaces,
+    block_size: usize,
+    player: &Player,
+    texture_cache: &TextureManager,
+    wall_shading: &WallShading,
+    game_state: &GameState,
+    keys: &[Key],
+    batteries: &[Battery],
+    font: &Font,
+    show_map: bool,
+    minimap_size: usize,
+    threads: usize,
+) {
+    {
+        // El mapa 2D está en coordenadas de mundo, así que va directo a la ventana
+        let target = if show_map { &mut *framebuffer } else { &mut *scene };
+        let width = target.width;
+        let height = target.height;
+        let half_height = height / 2;
+
+        // Cielo
+        target.set_current_color(Color::new(20, 20, 20, 255));
+        target.fill_rect(0, 0, width, half_height);
+
+        // Piso
+        target.set_current_color(Color::new(10, 10, 10, 255));
+        target.fill_rect(0, half_height, width, height - half_height);
+
+        if show_map {
+            render_maze(target, maze, doors, block_size, player);
+            // Dibujar baterías
+            for battery in batteries {
+                draw_sprite(target, player, &battery.sprite(), texture_cache, &[]);
+            }
+        } else {
+            let depth = render_3d(target, maze, doors, surfaces, block_size, player, texture_cache, wall_shading, threads);
+
+            // Llaves que quedan, meta y baterías, ocultos por las paredes
+            let mut sprites = collect_sprites(maze, keys, batteries, block_size);
+            draw_sprites(target, player, &mut sprites, texture_cache, &depth);
+        }
+
+        // --- APLICAR EFECTO DE LINTERNA ---
+        if game_state.flashlight_on {
+            apply_flashlight_effect(target, width, height, threads);
+        } else {
+            // Opcional: Si la linterna está apagada, aplicar un efecto de oscuridad general
+            apply_general_darkness(target, width, threads);
+        }
+        // --- FIN EFECTO ---
+    }
+
+    if !show_map {
+        framebuffer.blit_scaled(scene);
+    }
+
+    // Dibujar barra de vida
+    draw_life_bar(framebuffer, game_state, player, texture_cache, font);
+
+    if !show_map {
+        let minimap_position = (framebuffer.width - minimap_size as i32 - 20, 20);
+        render_minimap(framebuffer, maze, block_size, player, minimap_size, minimap_position);
+    }
+}
+
+// Valor de una opción `--flag valor` de la línea de comandos
+fn arg_value<'a>(args: &'a [String], flag: &str) -> Option<&'a str> {
+    args.iter()
+        .position(|arg| arg == flag)
+        .and_then(|i| args.get(i + 1))
+        .map(|value| value.as_str())
+}
+
 fn main() {
-    let window_width = 1300;
-    let window_height = 900;
-    let block_size = 100;
+    // Modo headless: renderiza un cuadro a PNG y termina, sin abrir ventana
+    let args: Vec<String> = std::env::args().collect();
+    if args.iter().any(|arg| arg == "--headless") {
+        let result = headless::HeadlessOptions::from_args(&args)
+            .map_err(|e| format!("{}\n{}", e, headless::HeadlessOptions::usage()))
+            .and_then(|options| headless::render_to_png(&options));
+        if let Err(e) = result {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    // Generar un laberinto y exportarlo: `--generate levels/random.txt --seed 7`
+    if let Some(path) = arg_value(&args, "--generate") {
+        let result = GeneratorOptions::from_args(&args)
+            .map_err(|e| format!("{}\n{}", e, GeneratorOptions::usage()))
+            .and_then(|options| {
+                generator::generate(&options)
+                    .export(path)
+                    .map_err(|e| format!("no se pudo escribir {}: {}", path, e))
+            });
+        if let Err(e) = result {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    // Opciones guardadas del usuario (las de fábrica si aún no hay archivo)
+    let settings_file = settings_path();
+    let mut settings = match &settings_file {
+        Some(path) => Settings::load(path).unwrap_or_else(|e| {
+            if !matches!(&e, SettingsError::Io(io) if io.kind() == std::io::ErrorKind::NotFound) {
+                eprintln!("{}: {}", path.display(), e);
+            }
+            Settings::default()
+        }),
+        None => Settings::default(),
+    };
+
+    // Tamaño de ventana y escala de render: `--window 1300x900 --render-scale 50`.
+    // `--window` vale solo para esta sesión: no se copia a las opciones guardadas
+    // y se descarta si se cambia el tamaño en la pantalla de opciones.
+    let mut window_override = arg_value(&args, "--window")
+        .and_then(|value| value.split_once('x'))
+        .and_then(|(w, h)| Some(clamp_window_size(w.parse().ok()?, h.parse().ok()?)));
+    let (window_width, window_height) = window_override.unwrap_or((settings.window_width, settings.window_height));
+    let mut render_scale = arg_value(&args, "--render-scale")
+        .and_then(|value| value.trim_end_matches('%').parse::<u32>().ok())
+        .unwrap_or(100)
+        .clamp(RENDER_SCALES[0], 100);
+    let render_threads = arg_value(&args, "--render-threads")
+        .and_then(|value| value.parse::<usize>().ok())
+        .unwrap_or_else(default_render_threads)
+        .clamp(1, MAX_RENDER_THREADS);
+    let block_size = BLOCK_SIZE;
 
     let (mut window, raylib_thread) = raylib::init()
         .size(window_width, window_height)
+        // Al agrandar la ventana el cuadro se escala según la opción de escalado
+        .resizable()
         .title("Raycaster Game - Encuentra la Llave!")
         .log_level(TraceLogLevel::LOG_WARNING)
         .build();
+    // ESC es una acción más (pausa/volver); la ventana no se cierra con ella
+    window.set_exit_key(None);
 
     let mut framebuffer = Framebuffer::new(
         window_width as i32, 
@@ -804,33 +1237,75 @@ fn main() {
 
     framebuffer.set_background_color(Color::new(80, 80, 200, 255));
 
+    // Framebuffer del mundo 3D a la resolución interna de render
+    let (scene_width, scene_height) = scaled_size(window_width, window_height, render_scale);
+    let mut scene = Framebuffer::new(scene_width, scene_height, Color::BLACK);
+
     let font = Font::new();
     let mut screen_state = ScreenState::MainMenu;
-    let mut selected_level = 1;
+    let mut levels: Vec<LevelInfo> = discover_levels(LEVELS_DIR);
+    let mut selected_level: usize = 0;
+    let mut menu_scroll: usize = 0;
+    // Algoritmo de la entrada "nivel aleatorio" (última del menú)
+    let mut random_algorithm = Algorithm::Backtracker;
+    // Último nivel aleatorio jugado (su semilla se muestra en el menú y se puede exportar)
+    let mut last_random: Option<GeneratedMaze> = None;
+    let mut export_message: Option<(String, Color)> = None;
+    // Nivel a cargar en este cuadro: Enter en el menú o `--level <archivo>`
+    let mut pending_level: Option<String> = arg_value(&args, "--level").map(String::from);
+    // Nivel ya cargado (o generado) que empieza en el próximo cuadro
+    let mut loaded: Option<(String, Result<Level, maze::MazeError>)> = None;
+    // Copia del nivel en juego, para reiniciarlo desde la pausa
+    let mut current_level: Option<Level> = None;
     let mut maze: Maze = Vec::new();
-    let mut player = Player{
-        pos: Vector2::new(150.0,150.0), 
-        a: PI/2.0,
-        fov: PI / 3.0, 
-    };
+    let mut surfaces = Surfaces::default();
+    // Último error al cargar un nivel, se muestra en el menú
+    let mut load_error: Option<String> = None;
+    let mut player = Player::new(Vector2::new(150.0, 150.0), PI / 2.0);
+    // Pantalla de opciones: fila seleccionada
+    let mut options_selected: usize = 0;
+    // Pantalla a la que vuelve la de opciones (menú principal o pausa)
+    let mut options_return = ScreenState::MainMenu;
+    let mut pause_selected: usize = 0;
+    // Último cuadro del juego, ya oscurecido, de fondo para el menú de pausa
+    let mut pause_backdrop: Option<Framebuffer> = None;
+
+    // Controles: los de `controls.txt` si existe, si no los de fábrica
+    let mut input = InputMap::load(CONTROLS_FILE).unwrap_or_else(|e| {
+        if !matches!(&e, ControlsError::Io(io) if io.kind() == std::io::ErrorKind::NotFound) {
+            eprintln!("{}: {}", CONTROLS_FILE, e);
+        }
+        InputMap::default()
+    });
+    // `--strafe`: A/D se desplazan de lado (las flechas siguen girando)
+    if args.iter().any(|arg| arg == "--strafe") {
+        input.use_strafe_keys();
+    }
+    // Pantalla de controles: acción seleccionada y si se espera la nueva entrada
+    let mut controls_selected: usize = 0;
+    let mut capturing = false;
 
     let texture_cache = TextureManager::new(&mut window, &raylib_thread);
     let mut game_state = GameState::new();
 
     let mut batteries: Vec<Battery> = Vec::new();
+    let mut keys: Vec<Key> = Vec::new();
+    let mut doors = Doors::default();
+
+    // Sombreado por cara de pared (este/oeste más oscuras)
+    let wall_shading = WallShading::default();
 
     let minimap_size = 150;
-    let minimap_position = (window_width as i32 - minimap_size as i32 - 20, 20);
 
     let audio_player = AudioPlayer::default();
     if let Err(e) = audio_player.play_background_music("assets/sounds/game_music.mp3") {
         eprintln!("Error al cargar la música de fondo: {}", e);
     }
-    audio_player.set_volume(0.3);
+    apply_settings(&settings, &mut player, &audio_player);
+    framebuffer.set_scale_mode(settings.scale_mode());
 
     // Control de cadencia para el sonido de pasos
     let mut last_step_time = Instant::now();
-    let step_cooldown = Duration::from_millis(250);
 
     let mut last_time = Instant::now();
 
@@ -841,7 +1316,39 @@ fn main() {
         // Calcular dt (delta time) aquí, en el ámbito del bucle principal
         let dt = current_time.duration_since(last_time).as_secs_f32();
         last_time = current_time; // Actualizar el tiempo anterior
-        
+
+        // Empezar el nivel pedido: `--level`, el menú o "reiniciar" en la pausa
+        if let Some(maze_file) = pending_level.take() {
+            let result = load_maze(&maze_file);
+            loaded = Some((maze_file, result));
+        }
+        if let Some((maze_file, result)) = loaded.take() {
+            match result {
+                Ok(level) => {
+                    current_level = Some(level.clone());
+                    game_state.reset(&level);
+                    game_state.par_time = navigation::par_time(&level, level.movement.speed / block_size as f32);
+                    // --- INICIALIZAR BATERÍAS DESPUÉS DE CARGAR EL LABERINTO ---
+                    batteries = level_batteries(&level, block_size, &mut thread_rng());
+                    keys = get_keys(&level, block_size);
+                    doors = Doors::from_maze(&level.maze);
+
+                    // Posicionar al jugador en la aparición del nivel (validada al cargar)
+                    player.respawn(cell_center(level.spawn, block_size), level.spawn_angle, level.movement);
+
+                    maze = level.maze;
+                    surfaces = level.surfaces;
+                    load_error = None;
+                    screen_state = ScreenState::Playing;
+                    audio_player.resume_all();
+                }
+                Err(e) => {
+                    eprintln!("No se pudo cargar {}: {}", maze_file, e);
+                    load_error = Some(format!("{}: {}", maze_file, e));
+                }
+            }
+        }
+
         match screen_state {
             ScreenState::MainMenu => {
                 // Mostrar cursor en el menú
@@ -850,48 +1357,92 @@ fn main() {
                     cursor_hidden = false;
                 }
 
-                if window.is_key_pressed(KeyboardKey::KEY_UP) {
-                    selected_level = if selected_level > 1 { selected_level - 1 } else { 3 };
+                // Niveles descubiertos más la entrada de nivel aleatorio
+                let entry_count = levels.len() + 1;
+                let random_selected = selected_level == levels.len();
+                // La acción "volver" en el menú principal cierra el juego
+                if input.pressed(&window, Action::Back) {
+                    break;
                 }
-                if window.is_key_pressed(KeyboardKey::KEY_DOWN) {
-                    selected_level = if selected_level < 3 { selected_level + 1 } else { 1 };
+                if input.pressed(&window, Action::Controls) {
+                    controls_selected = 0;
+                    capturing = false;
+                    screen_state = ScreenState::Controls;
                 }
-                if window.is_key_pressed(KeyboardKey::KEY_ENTER) {
-                    let maze_file = match selected_level {
-                        1 => "maze1.txt",
-                        2 => "maze2.txt",
-                        3 => "maze3.txt",
-                        _ => "maze1.txt",
-                    };
-                    
-                    maze = load_maze(maze_file);
-                    game_state.reset();
-                    // --- INICIALIZAR BATERÍAS DESPUÉS DE CARGAR EL LABERINTO ---
-                    batteries = initialize_batteries(&maze, block_size);
-                    
-                    // Posicionar al jugador en un lugar seguro
-                    for (j, row) in maze.iter().enumerate() {
-                        for (i, &cell) in row.iter().enumerate() {
-                            if cell == ' ' {
-                                player.pos.x = (i * block_size + block_size / 2) as f32;
-                                player.pos.y = (j * block_size + block_size / 2) as f32;
-                                break;
+                if input.pressed(&window, Action::Options) {
+                    options_selected = 0;
+                    options_return = ScreenState::MainMenu;
+                    screen_state = ScreenState::Options;
+                }
+                if input.pressed(&window, Action::MenuUp) {
+                    selected_level = if selected_level > 0 { selected_level - 1 } else { entry_count - 1 };
+                }
+                if input.pressed(&window, Action::MenuDown) {
+                    selected_level = if selected_level + 1 < entry_count { selected_level + 1 } else { 0 };
+                }
+                // Desplazar la lista para que la selección siempre sea visible
+                if selected_level < menu_scroll {
+                    menu_scroll = selected_level;
+                } else if selected_level >= menu_scroll + MENU_VISIBLE_LEVELS {
+                    menu_scroll = selected_level + 1 - MENU_VISIBLE_LEVELS;
+                }
+                // IZQUIERDA/DERECHA: elegir el algoritmo del nivel aleatorio
+                if random_selected {
+                    let current = Algorithm::ALL.iter().position(|&a| a == random_algorithm).unwrap_or(0);
+                    let count = Algorithm::ALL.len();
+                    if input.pressed(&window, Action::MenuRight) {
+                        random_algorithm = Algorithm::ALL[(current + 1) % count];
+                    }
+                    if input.pressed(&window, Action::MenuLeft) {
+                        random_algorithm = Algorithm::ALL[(current + count - 1) % count];
+                    }
+                }
+                // Cambiar la resolución interna del raycasting
+                if input.pressed(&window, Action::RenderScale) {
+                    let next = RENDER_SCALES.iter().position(|&s| s == render_scale).map_or(0, |i| i + 1);
+                    render_scale = RENDER_SCALES[next % RENDER_SCALES.len()];
+                    let (scene_width, scene_height) = scaled_size(framebuffer.width, framebuffer.height, render_scale);
+                    scene = Framebuffer::new(scene_width, scene_height, Color::BLACK);
+                }
+                if input.pressed(&window, Action::Confirm) {
+                    if let Some(level) = levels.get(selected_level) {
+                        pending_level = Some(level.path.clone());
+                    } else {
+                        let options = GeneratorOptions {
+                            algorithm: random_algorithm,
+                            seed: thread_rng().r#gen(),
+                            ..GeneratorOptions::default()
+                        };
+                        let generated = generator::generate(&options);
+                        loaded = Some((generated.name(), parse_level(&generated.to_level_text(), &generated.name())));
+                        last_random = Some(generated);
+                        export_message = None;
+                    }
+                }
+                // Guardar el último nivel aleatorio como archivo de nivel; aparece en la lista
+                if input.pressed(&window, Action::ExportLevel)
+                    && let Some(generated) = &last_random
+                {
+                    let path = format!("{}/{}", LEVELS_DIR, generated.file_name());
+                    export_message = Some(match generated.export(&path) {
+                        Ok(()) => {
+                            levels = discover_levels(LEVELS_DIR);
+                            if random_selected {
+                                selected_level = levels.len();
                             }
+                            (format!("EXPORTADO A {}", path.to_uppercase()), Color::GREEN)
                         }
-                    }
-                    
-                    screen_state = ScreenState::Playing;
+                        Err(e) => {
+                            eprintln!("No se pudo escribir {}: {}", path, e);
+                            (format!("NO SE PUDO ESCRIBIR {}", path.to_uppercase()), Color::RED)
+                        }
+                    });
                 }
-                
                 framebuffer.clear();
                 
                 // Dibujar menú principal
                 framebuffer.set_current_color(Color::new(20, 20, 40, 255));
-                for y in 0..framebuffer.height {
-                    for x in 0..framebuffer.width {
-                        framebuffer.set_pixel(x, y);
-                    }
-                }
+                framebuffer.fill_rect(0, 0, framebuffer.width, framebuffer.height);
                 
                 let screen_width = framebuffer.width;
                 
@@ -899,32 +1450,139 @@ fn main() {
                     screen_width / 2 - 45, 100, 2, Color::YELLOW);
                 font.draw_text(&mut framebuffer, "ENCUENTRA LA LLAVE A TIEMPO!", 
                     screen_width / 2 - 80, 150, 1, Color::GOLD);
-                font.draw_text(&mut framebuffer, "SELECCIONA NIVEL", 
+                font.draw_text(&mut framebuffer, &format!("SELECCIONA NIVEL ({}/{})", selected_level + 1, levels.len() + 1),
                     screen_width / 2 - 45, 220, 1, Color::WHITE);
+                if levels.is_empty() {
+                    font.draw_text(&mut framebuffer, &format!("NO HAY NIVELES EN {}/", LEVELS_DIR.to_uppercase()),
+                        screen_width / 2 - 80, 245, 1, Color::RED);
+                }
+
+                let random_entry = format!("NIVEL ALEATORIO ({})", random_algorithm.name().to_uppercase());
+                let names = levels.iter().map(|level| level.name.to_uppercase()).chain(std::iter::once(random_entry));
+                let visible = names.enumerate().skip(menu_scroll).take(MENU_VISIBLE_LEVELS);
+                for (row, (index, name)) in visible.enumerate() {
+                    let y_pos = 280 + row as i32 * 25;
 
-                for level in 1..=3 {
-                    let y_pos = 280 + (level - 1) * 50;
-                    
-                    if level == selected_level {
-                        font.draw_text(&mut framebuffer, &format!("> NIVEL {} <", level), 
+                    if index == selected_level {
+                        font.draw_text(&mut framebuffer, &format!("> {} <", name),
                             screen_width / 2 - 35, y_pos, 1, Color::GREEN);
                     } else {
-                        font.draw_text(&mut framebuffer, &format!("  NIVEL {}  ", level), 
+                        font.draw_text(&mut framebuffer, &format!("  {}  ", name),
                             screen_width / 2 - 35, y_pos, 1, Color::LIGHTGRAY);
                     }
                 }
 
+                font.draw_text(&mut framebuffer, &format!("RESOLUCION: {}% ({})   CONTROLES ({})   OPCIONES ({})", render_scale,
+                    binding_label(&input, Action::RenderScale), binding_label(&input, Action::Controls),
+                    binding_label(&input, Action::Options)),
+                    screen_width / 2 - 150, 420, 1, Color::SKYBLUE);
+
+                if let Some(generated) = &last_random {
+                    font.draw_text(&mut framebuffer, &format!("ULTIMO ALEATORIO: {} SEMILLA {}   EXPORTAR ({})",
+                        generated.options.algorithm.name().to_uppercase(), generated.options.seed,
+                        binding_label(&input, Action::ExportLevel)),
+                        screen_width / 2 - 150, 490, 1, Color::SKYBLUE);
+                }
+                if let Some((message, color)) = &export_message {
+                    font.draw_text(&mut framebuffer, message, screen_width / 2 - 150, 530, 1, *color);
+                }
+
+                if let Some(error) = &load_error {
+                    font.draw_text(&mut framebuffer, &error.to_uppercase(),
+                        screen_width / 2 - 150, 510, 1, Color::RED);
+                }
+
                 font.draw_text(&mut framebuffer, "Tienes 60 segundos para encontrar la llave", 
                     screen_width / 2 - 150, 450, 1, Color::LIGHTGRAY);
                 font.draw_text(&mut framebuffer, "y llegar a la salida (casilla verde)", 
                     screen_width / 2 - 120, 470, 1, Color::LIGHTGRAY);
             }
             
+            ScreenState::Controls => {
+                let actions: Vec<Action> = Action::ALL.into_iter().filter(|a| !a.is_axis()).collect();
+                if capturing {
+                    // ESC siempre cancela, para no quedar atrapado con controles rotos
+                    if window.is_key_pressed(KeyboardKey::KEY_ESCAPE) {
+                        capturing = false;
+                    } else if let Some(binding) = capture_binding(&mut window) {
+                        input.set_primary(actions[controls_selected], binding);
+                        capturing = false;
+                    }
+                } else {
+                    if input.pressed(&window, Action::MenuUp) {
+                        controls_selected = if controls_selected > 0 { controls_selected - 1 } else { actions.len() - 1 };
+                    }
+                    if input.pressed(&window, Action::MenuDown) {
+                        controls_selected = (controls_selected + 1) % actions.len();
+                    }
+                    if input.pressed(&window, Action::Confirm) {
+                        capturing = true;
+                    }
+                    // R siempre restaura los controles de fábrica
+                    if window.is_key_pressed(KeyboardKey::KEY_R) {
+                        input = InputMap::default();
+                    }
+                    if input.pressed(&window, Action::Back) {
+                        if let Err(e) = input.save(CONTROLS_FILE) {
+                            eprintln!("No se pudieron guardar los controles en {}: {}", CONTROLS_FILE, e);
+                        }
+                        screen_state = ScreenState::MainMenu;
+                    }
+                }
+
+                framebuffer.clear();
+                draw_controls_screen(&mut framebuffer, &font, &input, &actions, controls_selected, capturing);
+            }
+
+            ScreenState::Options => {
+                let count = SettingField::ALL.len();
+                if input.pressed(&window, Action::MenuUp) {
+                    options_selected = if options_selected > 0 { options_selected - 1 } else { count - 1 };
+                }
+                if input.pressed(&window, Action::MenuDown) {
+                    options_selected = (options_selected + 1) % count;
+                }
+                let field = SettingField::ALL[options_selected];
+                let saved_size = (settings.window_width, settings.window_height);
+                if input.pressed(&window, Action::MenuRight) {
+                    field.adjust(&mut settings, 1);
+                }
+                if input.pressed(&window, Action::MenuLeft) {
+                    field.adjust(&mut settings, -1);
+                }
+                if (settings.window_width, settings.window_height) != saved_size {
+                    window_override = None;
+                }
+                // Los cambios se ven al instante
+                apply_settings(&settings, &mut player, &audio_player);
+                let (width, height) = window_override.unwrap_or((settings.window_width, settings.window_height));
+                if (width, height) != (framebuffer.width, framebuffer.height) {
+                    window.set_window_size(width, height);
+                    framebuffer = Framebuffer::new(width, height, Color::new(50, 50, 100, 255));
+                    framebuffer.set_background_color(Color::new(80, 80, 200, 255));
+                    let (scene_width, scene_height) = scaled_size(framebuffer.width, framebuffer.height, render_scale);
+                    scene = Framebuffer::new(scene_width, scene_height, Color::BLACK);
+                }
+                framebuffer.set_scale_mode(settings.scale_mode());
+                if input.pressed(&window, Action::Back) {
+                    match &settings_file {
+                        Some(path) => if let Err(e) = settings.save(path) {
+                            eprintln!("No se pudieron guardar las opciones en {}: {}", path.display(), e);
+                        },
+                        None => eprintln!("No se encontró la carpeta de configuración; las opciones no se guardan"),
+                    }
+                    screen_state = options_return;
+                }
+
+                framebuffer.clear();
+                draw_options_screen(&mut framebuffer, &font, &input, &settings, options_selected);
+            }
+
             ScreenState::Playing => {
                 framebuffer.clear();
                 
-                // Actualizar vida
-                game_state.update_life();
+                // El reloj solo corre mientras se juega; `game_dt` mueve el mundo
+                let game_dt = game_state.update_clock(dt);
                 
                 // Ocultar cursor cuando se está jugando
                 if !cursor_hidden {
@@ -939,12 +1597,12 @@ fn main() {
                 }
 
                 // Actualizar baterías (movimiento y animación)
-                update_batteries(&mut batteries, &maze, block_size, dt);
+                update_batteries(&mut batteries, &maze, &player, block_size, game_dt);
                 
-                let keys = get_keys();
-                if check_key_collision(&player, &keys, &mut game_state, block_size) {
-                    // La llave fue recolectada (se muestra en la UI)
-                }
+                doors.update(player.pos, block_size, &game_state.inventory, game_dt);
+
+                // Las llaves recogidas pasan al inventario (se muestran en el HUD)
+                check_key_collision(&player, &mut keys, &mut game_state, block_size);
 
                 
                 
@@ -954,33 +1612,14 @@ fn main() {
                 }
 
                 // --- GESTIÓN DE LA LINTERNA ---
-                if window.is_key_pressed(KeyboardKey::KEY_E) { // Usamos 'E' para encender/apagar
+                if input.pressed(&window, Action::ToggleFlashlight) {
                     game_state.flashlight_on = !game_state.flashlight_on;
                 }
                 
-                // Renderizado normal del juego
-                let half_height = window_height as u32 / 2;
-                
-                // Cielo
-                framebuffer.set_current_color(Color::new(20, 20, 20, 255));
-                for y in 0..half_height {
-                    for x in 0..window_width as u32 {
-                        framebuffer.set_pixel(x as i32, y as i32);
-                    }
-                }
-                
-                // Piso
-                framebuffer.set_current_color(Color::new(10, 10, 10, 255));
-                for y in half_height..window_height as u32 {
-                    for x in 0..window_width as u32 {
-                        framebuffer.set_pixel(x as i32, y as i32);
-                    }
-                }
-
-                let moved = process_events(&window, &mut player, &maze, block_size);
-                if moved && (window.is_key_down(KeyboardKey::KEY_UP) || window.is_key_down(KeyboardKey::KEY_DOWN) || window.is_key_down(KeyboardKey::KEY_A) || window.is_key_down(KeyboardKey::KEY_S)) {
+                let walked = process_events(&window, &input, &mut player, &maze, &doors, block_size, game_dt);
+                if walked {
                     let now = Instant::now();
-                    if now.duration_since(last_step_time) >= step_cooldown {
+                    if now.duration_since(last_step_time) >= Duration::from_millis(settings.step_cooldown_ms) {
                         if let Err(e) = audio_player.play_sfx_once("assets/sounds/step.mp3") {
                             eprintln!("Error al reproducir sonido de paso: {}", e);
                         }
@@ -988,63 +1627,98 @@ fn main() {
                     }
                 }
 
-                
+                // Verificar colisión con baterías
+                check_battery_collision(&player, &mut batteries, &mut game_state, block_size);
 
-                if window.is_key_down(KeyboardKey::KEY_M) {
-                    render_maze(&mut framebuffer, &maze, block_size, &player);
-                } else {
-                    render_3d(&mut framebuffer, &maze, block_size, &player, &texture_cache);
-                    
-                    // Renderizar llaves si no han sido recolectadas
-                    if !game_state.has_key {
-                        for key in &keys {
-                            draw_sprite(&mut framebuffer, &player, key, &texture_cache);
-                        }
+                // Sonido de cada objeto recogido en este cuadro (llaves y baterías)
+                for item in game_state.inventory.take_pickups() {
+                    if let Err(e) = audio_player.play_sfx_once(item.pickup_sound()) {
+                        eprintln!("Error al reproducir sonido de {:?}: {}", item, e);
                     }
-                    
-                    // Renderizar la meta como sprite (siempre visible)
-                    draw_goal_sprite(&mut framebuffer, &player, &maze, &texture_cache, block_size);
-
                 }
-                // Dibujar baterías
-                for battery in &batteries {
-                    draw_battery(&mut framebuffer, &player, battery, &texture_cache);
+
+                render_frame(
+                    &mut framebuffer,
+                    &mut scene,
+                    &maze,
+                    &doors,
+                    &surfaces,
+                    block_size,
+                    &player,
+                    &texture_cache,
+                    &wall_shading,
+                    &game_state,
+                    &keys,
+                    &batteries,
+                    &font,
+                    input.down(&window, Action::ShowMap),
+                    minimap_size,
+                    render_threads,
+                );
+                
+                // Pausa: el reloj, las baterías, las puertas y el audio quedan congelados
+                if input.pressed(&window, Action::Pause) {
+                    pause_selected = 0;
+                    game_state.clock.set_paused(true);
+                    audio_player.pause_all();
+                    // El cuadro congelado se oscurece una sola vez y se reutiliza mientras dure la pausa
+                    let mut backdrop = Framebuffer::new(framebuffer.width, framebuffer.height, Color::BLACK);
+                    backdrop.blit_scaled(&framebuffer);
+                    dim_frame(&mut backdrop);
+                    pause_backdrop = Some(backdrop);
+                    screen_state = ScreenState::Paused;
                 }
+            }
 
-                // Verificar colisión con baterías
-                if check_battery_collision(&player, &mut batteries, &mut game_state, block_size) {
-                    // Batería recolectada (opcional: reproducir sonido)
-                    if let Err(e) = audio_player.play_sfx_once("assets/sounds/battery_pickup.mp3") { // Asegúrate de tener este archivo
-                        eprintln!("Error al reproducir sonido de batería: {}", e);
-                    }
+            ScreenState::Paused => {
+                if cursor_hidden {
+                    window.enable_cursor();
+                    cursor_hidden = false;
                 }
 
-                // --- APLICAR EFECTO DE LINTERNA ---
-                if game_state.flashlight_on {
-                    apply_flashlight_effect(&mut framebuffer, window_width, window_height);
-                } else {
-                    // Opcional: Si la linterna está apagada, aplicar un efecto de oscuridad general
-                    apply_general_darkness(&mut framebuffer, window_width, window_height);
+                let count = PauseItem::ALL.len();
+                if input.pressed(&window, Action::MenuUp) {
+                    pause_selected = if pause_selected > 0 { pause_selected - 1 } else { count - 1 };
                 }
-                // --- FIN EFECTO ---
-                
-                // Dibujar barra de vida
-                draw_life_bar(&mut framebuffer, &game_state, &font);
-                
-                if !window.is_key_down(KeyboardKey::KEY_M) {
-                    render_minimap(
-                        &mut framebuffer, 
-                        &maze, 
-                        block_size, 
-                        &player, 
-                        minimap_size, 
-                        minimap_position
-                    );
+                if input.pressed(&window, Action::MenuDown) {
+                    pause_selected = (pause_selected + 1) % count;
                 }
-                
-                if window.is_key_pressed(KeyboardKey::KEY_ESCAPE) {
-                    screen_state = ScreenState::MainMenu;
+                let chosen = if input.pressed(&window, Action::Pause) || input.pressed(&window, Action::Back) {
+                    Some(PauseItem::Resume)
+                } else if input.pressed(&window, Action::Confirm) {
+                    Some(PauseItem::ALL[pause_selected])
+                } else {
+                    None
+                };
+                match chosen {
+                    Some(PauseItem::Resume) => {
+                        game_state.clock.set_paused(false);
+                        audio_player.resume_all();
+                        screen_state = ScreenState::Playing;
+                    }
+                    // Se vuelve a cargar la copia del nivel en el próximo cuadro
+                    Some(PauseItem::Restart) => {
+                        loaded = current_level.clone().map(|level| (level.name.clone(), Ok(level)));
+                    }
+                    Some(PauseItem::Options) => {
+                        options_selected = 0;
+                        options_return = ScreenState::Paused;
+                        screen_state = ScreenState::Options;
+                    }
+                    Some(PauseItem::Quit) => {
+                        audio_player.resume_all();
+                        screen_state = ScreenState::MainMenu;
+                    }
+                    None => {}
+                }
+
+                // El juego congelado de fondo, oscurecido, con el menú encima
+                framebuffer.clear();
+                if let Some(backdrop) = &pause_backdrop {
+                    // Se escala por si la ventana cambió de tamaño en las opciones
+                    framebuffer.blit_scaled(backdrop);
                 }
+                draw_pause_menu(&mut framebuffer, &font, &input, pause_selected);
             }
             
             ScreenState::Win => {
@@ -1057,10 +1731,10 @@ fn main() {
                     cursor_hidden = false;
                 }
                 
-                if window.is_key_pressed(KeyboardKey::KEY_SPACE) {
+                if input.pressed(&window, Action::Restart) {
                     screen_state = ScreenState::MainMenu;
                 }
-                if window.is_key_pressed(KeyboardKey::KEY_ESCAPE) {
+                if input.pressed(&window, Action::Back) {
                     screen_state = ScreenState::MainMenu;
                 }
             }
@@ -1075,10 +1749,10 @@ fn main() {
                     cursor_hidden = false;
                 }
                 
-                if window.is_key_pressed(KeyboardKey::KEY_SPACE) {
+                if input.pressed(&window, Action::Restart) {
                     screen_state = ScreenState::MainMenu;
                 }
-                if window.is_key_pressed(KeyboardKey::KEY_ESCAPE) {
+                if input.pressed(&window, Action::Back) {
                     screen_state = ScreenState::MainMenu;
                 }
             }
@@ -1091,4 +1765,67 @@ fn main() {
     if cursor_hidden {
         window.enable_cursor();
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Partida recién empezada con el jugador en (1,1) y una batería en `battery_cell`
+    fn setup(battery_cell: (usize, usize)) -> (GameState, Player, Vec<Battery>) {
+        let pos = cell_center(battery_cell, BLOCK_SIZE);
+        let player = Player::new(cell_center((1, 1), BLOCK_SIZE), 0.0);
+        (GameState::new(), player, vec![Battery::new(pos.x, pos.y, ['b', 'c', 'd'])])
+    }
+
+    #[test]
+    fn battery_pickup_extends_the_run() {
+        let (mut game_state, player, mut batteries) = setup((1, 1));
+
+        game_state.update_clock(DEFAULT_TIME_LIMIT - 10.0);
+        assert!(check_battery_collision(&player, &mut batteries, &mut game_state, BLOCK_SIZE));
+        assert!(batteries.is_empty());
+        assert_eq!(game_state.inventory.count(Item::Battery), 1);
+
+        // El cuadro siguiente descuenta su dt sin pisar la bonificación, y sin
+        // la batería el tiempo se habría acabado a los 10 s
+        game_state.update_clock(0.5);
+        assert_eq!(game_state.life(), 10.0 + BATTERY_TIME_BONUS - 0.5);
+        game_state.update_clock(14.5);
+        assert!(game_state.is_alive());
+        assert_eq!(game_state.life(), 10.0 + BATTERY_TIME_BONUS - 15.0);
+    }
+
+    #[test]
+    fn battery_bonus_stops_at_the_level_limit() {
+        let (mut game_state, player, mut batteries) = setup((1, 1));
+
+        game_state.update_clock(5.0);
+        check_battery_collision(&player, &mut batteries, &mut game_state, BLOCK_SIZE);
+        assert_eq!(game_state.life(), game_state.max_life());
+    }
+
+    #[test]
+    fn distant_battery_is_not_collected() {
+        let (mut game_state, player, mut batteries) = setup((4, 1));
+
+        game_state.update_clock(30.0);
+        assert!(!check_battery_collision(&player, &mut batteries, &mut game_state, BLOCK_SIZE));
+        assert_eq!(batteries.len(), 1);
+        assert_eq!(game_state.life(), DEFAULT_TIME_LIMIT - 30.0);
+        assert_eq!(game_state.inventory.count(Item::Battery), 0);
+    }
+
+    #[test]
+    fn paused_clock_keeps_the_bonus() {
+        let (mut game_state, player, mut batteries) = setup((1, 1));
+
+        game_state.update_clock(DEFAULT_TIME_LIMIT - 10.0);
+        check_battery_collision(&player, &mut batteries, &mut game_state, BLOCK_SIZE);
+        game_state.clock.set_paused(true);
+        assert_eq!(game_state.update_clock(100.0), 0.0);
+        game_state.clock.set_paused(false);
+        assert_eq!(game_state.life(), 10.0 + BATTERY_TIME_BONUS);
+        assert_eq!(game_state.inventory.count(Item::Battery), 1);
+    }
+}
@@ -1,14 +1,657 @@
-use std::fs::File;
-use std::io::{BufRead, BufReader};
+use std::collections::HashMap;
+use std::f32::consts::PI;
+use std::fmt;
+use std::io;
+use std::path::Path;
+use crate::navigation::{distance_field, reachable_with_keys};
+use crate::key::KeyColor;
+use crate::player::{Movement, MAX_PLAYER_RADIUS};
 
 pub type Maze = Vec<Vec<char>>;
 
-pub fn load_maze(filename: &str) -> Maze {
-    let file = File::open(filename).unwrap();
-    let reader = BufReader::new(file);
+// Glifo de techo que deja ver el cielo
+pub const SKYLIGHT: char = 'o';
 
-    reader
-        .lines()
-        .map(|line| line.unwrap().chars().collect())
+// Valores por defecto de los metadatos cuando el archivo no los declara
+pub const DEFAULT_TIME_LIMIT: f32 = 120.0;
+pub const DEFAULT_BATTERIES: usize = 2;
+const DEFAULT_KEY_CELL: (usize, usize) = (2, 2);
+pub const DEFAULT_SPAWN_ANGLE: f32 = PI / 2.0; // Mirando al sur
+
+// Capas opcionales de piso y techo: un glifo por celda, con las mismas
+// dimensiones que la cuadrícula de paredes. Se declaran después del laberinto
+// con las secciones `[floor]` y `[ceiling]`.
+#[derive(Default, Clone)]
+pub struct Surfaces {
+    pub floor: Vec<Vec<char>>,
+    pub ceiling: Vec<Vec<char>>,
+}
+
+impl Surfaces {
+    // ' ' significa "sin textura" (se usa el color plano de fondo)
+    pub fn ceiling_at(&self, x: usize, y: usize) -> char {
+        layer_at(&self.ceiling, x, y)
+    }
+}
+
+fn layer_at(layer: &[Vec<char>], x: usize, y: usize) -> char {
+    layer.get(y).and_then(|row| row.get(x)).copied().unwrap_or(' ')
+}
+
+// Glifos válidos en las capas de piso y techo (ver `SURFACE_FILES` en textures.rs)
+const SURFACE_GLYPHS: [char; 6] = [' ', '.', '=', '~', '#', SKYLIGHT];
+
+// Glifos válidos en la cuadrícula de paredes
+const WALL_GLYPHS: [char; 4] = ['+', '-', '|', '#'];
+const OPEN_GLYPHS: [char; 2] = [' ', 'g'];
+
+// Puerta corrediza sin cerradura; las puertas con cerradura usan la letra de
+// su llave en mayúscula (ver `KeyColor::gate_glyph`)
+pub const DOOR: char = 'D';
+
+pub fn is_door(cell: char) -> bool {
+    cell == DOOR || KeyColor::from_gate_glyph(cell).is_some()
+}
+
+// Entidades colocadas con glifos en la cuadrícula. Al cargar se extraen a
+// `Level::entities` y su celda queda como piso (' ').
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EntityKind {
+    Key(KeyColor),
+    Battery,
+    Spawn { angle: f32 },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Entity {
+    pub kind: EntityKind,
+    pub cell: (usize, usize), // (columna, fila)
+}
+
+fn entity_for_glyph(glyph: char) -> Option<EntityKind> {
+    match glyph {
+        'k' | 'r' | 'u' => KeyColor::from_key_glyph(glyph).map(EntityKind::Key),
+        'b' => Some(EntityKind::Battery),
+        '@' | 'p' => Some(EntityKind::Spawn { angle: DEFAULT_SPAWN_ANGLE }),
+        // Aparición con orientación explícita
+        '>' => Some(EntityKind::Spawn { angle: 0.0 }),
+        'v' => Some(EntityKind::Spawn { angle: PI / 2.0 }),
+        '<' => Some(EntityKind::Spawn { angle: PI }),
+        '^' => Some(EntityKind::Spawn { angle: -PI / 2.0 }),
+        _ => None,
+    }
+}
+
+// Saca los glifos de entidad de la cuadrícula y los reemplaza por piso
+fn extract_entities(maze: &mut Maze) -> Vec<Entity> {
+    let mut entities = Vec::new();
+    for (row, cells) in maze.iter_mut().enumerate() {
+        for (col, cell) in cells.iter_mut().enumerate() {
+            if let Some(kind) = entity_for_glyph(*cell) {
+                entities.push(Entity { kind, cell: (col, row) });
+                *cell = ' ';
+            }
+        }
+    }
+    entities
+}
+
+#[derive(Debug)]
+pub enum MazeError {
+    Io(io::Error),
+    Empty,
+    UnknownGlyph { row: usize, col: usize, glyph: char },
+    NotRectangular { row: usize, expected: usize, found: usize },
+    OpenBorder { row: usize, col: usize },
+    NoSpawn,
+    NoReachableGoal,
+    LayerSize { layer: &'static str, expected: usize, found: usize },
+    InvalidLayer { layer: &'static str, error: Box<MazeError> },
+    InvalidMetadata { line: String },
+    BlockedCell { what: &'static str, col: usize, row: usize },
+    UnreachableKey,
+    MultipleSpawns,
+    MissingExitKey(KeyColor),
+    LockedKey(KeyColor),
+    LockedGoal,
+}
+
+impl fmt::Display for MazeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MazeError::Io(e) => write!(f, "no se pudo leer el archivo: {}", e),
+            MazeError::Empty => write!(f, "el laberinto esta vacio"),
+            MazeError::UnknownGlyph { row, col, glyph } => {
+                write!(f, "glifo desconocido {:?} en fila {}, columna {}", glyph, row + 1, col + 1)
+            }
+            MazeError::NotRectangular { row, expected, found } => write!(
+                f,
+                "la fila {} tiene {} columnas, se esperaban {}",
+                row + 1,
+                found,
+                expected
+            ),
+            MazeError::OpenBorder { row, col } => {
+                write!(f, "borde abierto en fila {}, columna {}", row + 1, col + 1)
+            }
+            MazeError::NoSpawn => write!(f, "no hay celda libre para el jugador"),
+            MazeError::NoReachableGoal => write!(f, "no hay meta alcanzable"),
+            MazeError::LayerSize { layer, expected, found } => {
+                write!(f, "la capa [{}] tiene {} filas, se esperaban {}", layer, found, expected)
+            }
+            MazeError::InvalidLayer { layer, error } => write!(f, "capa [{}]: {}", layer, error),
+            MazeError::InvalidMetadata { line } => write!(f, "metadato invalido: {}", line),
+            MazeError::BlockedCell { what, col, row } => {
+                write!(f, "{} en una pared (columna {}, fila {})", what, col, row)
+            }
+            MazeError::UnreachableKey => write!(f, "la llave no es alcanzable"),
+            MazeError::MultipleSpawns => write!(f, "hay mas de una aparicion en la cuadricula"),
+            MazeError::MissingExitKey(color) => {
+                write!(f, "la salida pide la llave {} pero el nivel no tiene ninguna", color.name())
+            }
+            MazeError::LockedKey(color) => {
+                write!(f, "la llave {} queda detras de una puerta que no se puede abrir antes", color.name())
+            }
+            MazeError::LockedGoal => write!(f, "la meta queda detras de una puerta sin llave alcanzable"),
+        }
+    }
+}
+
+impl std::error::Error for MazeError {}
+
+impl From<io::Error> for MazeError {
+    fn from(e: io::Error) -> Self {
+        MazeError::Io(e)
+    }
+}
+
+// Celdas por las que se puede caminar. Las puertas cuentan como transitables
+// (el estado abierto/cerrado se lleva aparte, en `door::Doors`).
+pub fn is_walkable(cell: char) -> bool {
+    OPEN_GLYPHS.contains(&cell) || is_door(cell)
+}
+
+// Celda de aparición del jugador: la primera celda libre de la última fila
+// que tenga alguna. Es la regla del bucle original del menú, cuyo `break`
+// solo salía de la fila actual, así que la última fila con celdas libres
+// sobrescribía a las anteriores.
+pub fn spawn_cell(maze: &Maze) -> Option<(usize, usize)> {
+    maze.iter()
+        .enumerate()
+        .rev()
+        .find_map(|(j, row)| row.iter().position(|&cell| cell == ' ').map(|i| (i, j)))
+}
+
+// Nivel cargado: cuadrícula, capas de piso/techo y metadatos. Las celdas
+// se expresan como (columna, fila).
+#[derive(Clone)]
+pub struct Level {
+    pub name: String,
+    pub maze: Maze,
+    pub surfaces: Surfaces,
+    pub time_limit: f32, // Segundos disponibles (también es el máximo de la barra)
+    pub batteries: usize, // Baterías al azar, solo si la cuadrícula no coloca ninguna
+    pub spawn: (usize, usize),
+    pub spawn_angle: f32,
+    pub entities: Vec<Entity>, // Llaves y baterías colocadas
+    pub exit_keys: Vec<KeyColor>, // Llaves necesarias para cruzar la meta
+    pub movement: Movement, // Velocidad, carrera, aceleración y fricción del jugador
+}
+
+impl Level {
+    pub fn cells_of(&self, kind: EntityKind) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.entities.iter().filter(move |e| e.kind == kind).map(|e| e.cell)
+    }
+
+    // Llaves colocadas, de cualquier color
+    pub fn keys(&self) -> impl Iterator<Item = (KeyColor, (usize, usize))> + '_ {
+        self.entities.iter().filter_map(|e| match e.kind {
+            EntityKind::Key(color) => Some((color, e.cell)),
+            _ => None,
+        })
+    }
+}
+
+// Comprueba que el laberinto sea rectangular, cerrado y con glifos conocidos
+fn validate_grid(maze: &Maze) -> Result<(), MazeError> {
+    let width = maze.first().map_or(0, |row| row.len());
+    if width == 0 {
+        return Err(MazeError::Empty);
+    }
+    let height = maze.len();
+
+    for (row, cells) in maze.iter().enumerate() {
+        if cells.len() != width {
+            return Err(MazeError::NotRectangular { row, expected: width, found: cells.len() });
+        }
+        for (col, &glyph) in cells.iter().enumerate() {
+            if !WALL_GLYPHS.contains(&glyph) && !is_walkable(glyph) {
+                return Err(MazeError::UnknownGlyph { row, col, glyph });
+            }
+            let on_border = row == 0 || col == 0 || row == height - 1 || col == width - 1;
+            if on_border && is_walkable(glyph) {
+                return Err(MazeError::OpenBorder { row, col });
+            }
+        }
+    }
+    Ok(())
+}
+
+// Una capa de piso o techo está ausente o tiene las dimensiones del laberinto,
+// con glifos de superficie conocidos
+fn validate_layer(layer: &'static str, cells: &[Vec<char>], maze: &Maze) -> Result<(), MazeError> {
+    if cells.is_empty() {
+        return Ok(());
+    }
+    if cells.len() != maze.len() {
+        return Err(MazeError::LayerSize { layer, expected: maze.len(), found: cells.len() });
+    }
+    let width = maze[0].len();
+    let invalid = |error| Err(MazeError::InvalidLayer { layer, error: Box::new(error) });
+    for (row, glyphs) in cells.iter().enumerate() {
+        if glyphs.len() != width {
+            return invalid(MazeError::NotRectangular { row, expected: width, found: glyphs.len() });
+        }
+        if let Some(col) = glyphs.iter().position(|glyph| !SURFACE_GLYPHS.contains(glyph)) {
+            return invalid(MazeError::UnknownGlyph { row, col, glyph: glyphs[col] });
+        }
+    }
+    Ok(())
+}
+
+fn check_walkable(maze: &Maze, what: &'static str, (col, row): (usize, usize)) -> Result<(), MazeError> {
+    let cell = maze.get(row).and_then(|r| r.get(col)).copied();
+    if cell.is_some_and(is_walkable) {
+        Ok(())
+    } else {
+        Err(MazeError::BlockedCell { what, col, row })
+    }
+}
+
+// Validación completa de un nivel: cuadrícula, aparición y entidades en
+// celdas libres, y llaves y meta alcanzables desde la aparición.
+pub fn validate_level(level: &Level) -> Result<(), MazeError> {
+    let maze = &level.maze;
+    validate_grid(maze)?;
+    check_walkable(maze, "aparicion", level.spawn)?;
+    for entity in &level.entities {
+        let what = match entity.kind {
+            EntityKind::Key(_) => "llave",
+            EntityKind::Battery => "bateria",
+            EntityKind::Spawn { .. } => "aparicion",
+        };
+        check_walkable(maze, what, entity.cell)?;
+    }
+
+    for &color in &level.exit_keys {
+        if !level.keys().any(|(held, _)| held == color) {
+            return Err(MazeError::MissingExitKey(color));
+        }
+    }
+
+    let reachable = distance_field(maze, &[level.spawn]);
+    if level.keys().any(|(_, cell)| !reachable.reachable(cell)) {
+        return Err(MazeError::UnreachableKey);
+    }
+    let goal_reachable = reachable.cells().any(|((x, y), _)| maze[y][x] == 'g');
+    if !goal_reachable {
+        return Err(MazeError::NoReachableGoal);
+    }
+
+    // Con las puertas cerradas hasta tener su llave: ¿se puede ganar?
+    let (unlocked, held) = reachable_with_keys(level);
+    if let Some(&color) = level.exit_keys.iter().find(|color| !held.contains(color)) {
+        return Err(MazeError::LockedKey(color));
+    }
+    if !unlocked.cells().any(|((x, y), _)| maze[y][x] == 'g') {
+        return Err(MazeError::LockedGoal);
+    }
+    Ok(())
+}
+
+fn is_section_header(line: &str) -> bool {
+    line.starts_with('[')
+}
+
+// Divide el texto en secciones `[nombre]`. Las líneas anteriores al primer
+// encabezado son la cuadrícula, igual que una sección `[maze]`.
+fn parse_sections(text: &str) -> HashMap<String, Vec<String>> {
+    let mut sections: HashMap<String, Vec<String>> = HashMap::new();
+    let mut section = String::from("maze");
+
+    for line in text.lines() {
+        let line = line.trim_end_matches('\r');
+        if is_section_header(line) {
+            section = line.trim().trim_start_matches('[').trim_end_matches(']').to_string();
+            continue;
+        }
+        sections.entry(section.clone()).or_default().push(line.to_string());
+    }
+
+    sections
+}
+
+fn read_sections(filename: &str) -> Result<HashMap<String, Vec<String>>, MazeError> {
+    let text = std::fs::read_to_string(filename)?;
+    Ok(parse_sections(&text))
+}
+
+fn parse_cell(line: &str, value: &str) -> Result<(usize, usize), MazeError> {
+    let invalid = || MazeError::InvalidMetadata { line: line.to_string() };
+    let (col, row) = value.split_once(',').ok_or_else(invalid)?;
+    Ok((
+        col.trim().parse().map_err(|_| invalid())?,
+        row.trim().parse().map_err(|_| invalid())?,
+    ))
+}
+
+// Lista de llaves de la salida: nombres separados por comas, o `none`
+fn parse_exit_keys(line: &str, value: &str) -> Result<Vec<KeyColor>, MazeError> {
+    if value == "none" {
+        return Ok(Vec::new());
+    }
+    value
+        .split(',')
+        .map(|name| {
+            KeyColor::from_name(name.trim()).ok_or_else(|| MazeError::InvalidMetadata { line: line.to_string() })
+        })
         .collect()
-}
\ No newline at end of file
+}
+
+fn parse_positive(value: &str) -> Option<f32> {
+    value.parse().ok().filter(|&v: &f32| v.is_finite() && v > 0.0)
+}
+
+// Línea de la cabecera sin el comentario (todo lo que sigue a ';')
+fn strip_comment(line: &str) -> &str {
+    line.split(';').next().unwrap_or("").trim()
+}
+
+// Cabecera `[level]` con líneas `clave = valor`; las líneas vacías y los
+// comentarios (desde ';') se ignoran. Devuelve la aparición y la llave declaradas.
+fn parse_metadata(
+    level: &mut Level,
+    lines: &[String],
+) -> Result<(Option<(usize, usize)>, Option<(usize, usize)>), MazeError> {
+    let mut spawn = None;
+    let mut key_cell = None;
+    for line in lines {
+        let trimmed = strip_comment(line);
+        if trimmed.is_empty() {
+            continue;
+        }
+        let invalid = || MazeError::InvalidMetadata { line: trimmed.to_string() };
+        let (key, value) = trimmed.split_once('=').ok_or_else(invalid)?;
+        let value = value.trim();
+        match key.trim() {
+            "name" => level.name = value.to_string(),
+            "time" => level.time_limit = parse_positive(value).ok_or_else(invalid)?,
+            "batteries" => level.batteries = value.parse().map_err(|_| invalid())?,
+            "spawn" => spawn = Some(parse_cell(trimmed, value)?),
+            "key" => key_cell = Some(parse_cell(trimmed, value)?),
+            "exit" => level.exit_keys = parse_exit_keys(trimmed, value)?,
+            "speed" => level.movement.speed = parse_positive(value).ok_or_else(invalid)?,
+            "sprint" => level.movement.sprint_factor = parse_positive(value).ok_or_else(invalid)?,
+            "acceleration" => level.movement.acceleration = parse_positive(value).ok_or_else(invalid)?,
+            "friction" => level.movement.friction = parse_positive(value).ok_or_else(invalid)?,
+            "radius" => {
+                level.movement.radius = parse_positive(value).filter(|&r| r <= MAX_PLAYER_RADIUS).ok_or_else(invalid)?
+            }
+            _ => return Err(invalid()),
+        }
+    }
+    Ok((spawn, key_cell))
+}
+
+// Cuadrícula de una sección, sin las líneas vacías que la separan de la siguiente
+fn to_grid(lines: Option<&Vec<String>>) -> Vec<Vec<char>> {
+    let mut grid: Vec<Vec<char>> = lines
+        .map(|lines| lines.iter().map(|line| line.chars().collect()).collect())
+        .unwrap_or_default();
+    while grid.last().is_some_and(|row| row.is_empty()) {
+        grid.pop();
+    }
+    grid
+}
+
+pub fn load_maze(filename: &str) -> Result<Level, MazeError> {
+    let text = std::fs::read_to_string(filename)?;
+    parse_level(&text, &default_name(filename))
+}
+
+// Interpreta el contenido de un archivo de nivel; `name` se usa si la
+// cabecera no declara uno.
+pub fn parse_level(text: &str, name: &str) -> Result<Level, MazeError> {
+    let sections = parse_sections(text);
+
+    let mut maze = to_grid(sections.get("maze"));
+    let mut entities = extract_entities(&mut maze);
+    validate_grid(&maze)?;
+
+    let surfaces = Surfaces {
+        floor: to_grid(sections.get("floor")),
+        ceiling: to_grid(sections.get("ceiling")),
+    };
+    validate_layer("floor", &surfaces.floor, &maze)?;
+    validate_layer("ceiling", &surfaces.ceiling, &maze)?;
+
+    let mut level = Level {
+        name: name.to_string(),
+        surfaces,
+        maze,
+        time_limit: DEFAULT_TIME_LIMIT,
+        batteries: DEFAULT_BATTERIES,
+        spawn: (0, 0),
+        spawn_angle: DEFAULT_SPAWN_ANGLE,
+        entities: Vec::new(),
+        exit_keys: vec![KeyColor::Gold],
+        movement: Movement::default(),
+    };
+    let (declared_spawn, declared_key) = match sections.get("level") {
+        Some(lines) => parse_metadata(&mut level, lines)?,
+        None => (None, None),
+    };
+
+    // Prioridad de la aparición: glifo en la cuadrícula, cabecera, primera celda libre
+    let mut spawns = entities.iter().filter_map(|e| match e.kind {
+        EntityKind::Spawn { angle } => Some((e.cell, angle)),
+        _ => None,
+    });
+    let glyph_spawn = spawns.next();
+    if spawns.next().is_some() {
+        return Err(MazeError::MultipleSpawns);
+    }
+    match glyph_spawn {
+        Some((cell, angle)) => {
+            level.spawn = cell;
+            level.spawn_angle = angle;
+        }
+        None => {
+            level.spawn = match declared_spawn {
+                Some(spawn) => spawn,
+                None => spawn_cell(&level.maze).ok_or(MazeError::NoSpawn)?,
+            };
+        }
+    }
+    entities.retain(|e| !matches!(e.kind, EntityKind::Spawn { .. }));
+
+    // Sin llave dorada en la cuadrícula se usa la de la cabecera (o la posición
+    // clásica, si la salida la pide)
+    let needs_gold = declared_key.is_some() || level.exit_keys.contains(&KeyColor::Gold);
+    if needs_gold && !entities.iter().any(|e| e.kind == EntityKind::Key(KeyColor::Gold)) {
+        let cell = declared_key.unwrap_or(DEFAULT_KEY_CELL);
+        entities.push(Entity { kind: EntityKind::Key(KeyColor::Gold), cell });
+    }
+    level.entities = entities;
+
+    validate_level(&level)?;
+    Ok(level)
+}
+
+// Nombre por defecto de un nivel: el nombre del archivo sin extensión
+fn default_name(filename: &str) -> String {
+    Path::new(filename)
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_uppercase())
+        .unwrap_or_default()
+}
+
+// Entrada del menú de niveles
+pub struct LevelInfo {
+    pub path: String,
+    pub name: String,
+}
+
+// Nombre declarado en la cabecera `[level]`, sin validar el resto del archivo
+fn read_level_name(filename: &str) -> Option<String> {
+    let sections = read_sections(filename).ok()?;
+    sections.get("level")?.iter().find_map(|line| {
+        let (key, value) = strip_comment(line).split_once('=')?;
+        (key.trim() == "name").then(|| value.trim().to_string())
+    })
+}
+
+// Busca archivos `.txt` en `dir`, ordenados por nombre de archivo. Los niveles
+// se validan al cargarlos, así que aquí solo se lee el nombre.
+pub fn discover_levels(dir: &str) -> Vec<LevelInfo> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut paths: Vec<_> = entries
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.is_file() && path.extension().is_some_and(|ext| ext == "txt"))
+        .collect();
+    paths.sort();
+
+    paths
+        .into_iter()
+        .map(|path| {
+            let path = path.to_string_lossy().to_string();
+            let name = read_level_name(&path).unwrap_or_else(|| default_name(&path));
+            LevelInfo { path, name }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn level(grid: &str) -> Result<Level, MazeError> {
+        parse_level(&format!("[level]\nexit = red\n[maze]\n{}", grid), "prueba")
+    }
+
+    // El recorrido original: para cada fila, la primera celda libre (el
+    // `break` no salía del bucle de filas)
+    fn baseline_spawn(maze: &Maze) -> Option<(usize, usize)> {
+        let mut spawn = None;
+        for (j, row) in maze.iter().enumerate() {
+            for (i, &cell) in row.iter().enumerate() {
+                if cell == ' ' {
+                    spawn = Some((i, j));
+                    break;
+                }
+            }
+        }
+        spawn
+    }
+
+    #[test]
+    fn spawn_cell_matches_the_baseline_loop() {
+        let maze: Maze = ["+-----+", "|  +  |", "|+   +|", "| +-+ |", "+-----+"]
+            .iter()
+            .map(|row| row.chars().collect())
+            .collect();
+        assert_eq!(spawn_cell(&maze), baseline_spawn(&maze));
+        assert_eq!(spawn_cell(&maze), Some((1, 3)));
+    }
+
+    #[test]
+    fn key_before_its_gate_is_winnable() {
+        let result = level("+-------+\n|@ r R g|\n+-------+\n");
+        assert!(result.is_ok(), "{:?}", result.err());
+    }
+
+    #[test]
+    fn key_behind_its_own_gate_is_rejected() {
+        let result = level("+-------+\n|@ R r g|\n+-------+\n");
+        assert!(matches!(result, Err(MazeError::LockedKey(KeyColor::Red))));
+    }
+
+    #[test]
+    fn unknown_glyph_reports_its_cell() {
+        let result = level("+-----+\n|@ r g|\n| ? +R|\n+-----+\n");
+        assert!(matches!(result, Err(MazeError::UnknownGlyph { row: 2, col: 2, glyph: '?' })));
+    }
+
+    #[test]
+    fn short_row_is_not_rectangular() {
+        let result = level("+-----+\n|@ r g|\n|   |\n+-----+\n");
+        assert!(matches!(result, Err(MazeError::NotRectangular { row: 2, expected: 7, found: 5 })));
+    }
+
+    #[test]
+    fn gap_in_the_wall_is_an_open_border() {
+        let result = level("+-----+\n|@ r g \n+-----+\n");
+        assert!(matches!(result, Err(MazeError::OpenBorder { row: 1, col: 6 })));
+    }
+
+    #[test]
+    fn maze_without_free_floor_has_no_spawn() {
+        let result = level("+---+\n|ggg|\n+---+\n");
+        assert!(matches!(result, Err(MazeError::NoSpawn)));
+    }
+
+    #[test]
+    fn walled_off_goal_is_unreachable() {
+        let result = level("+-----+\n|@ r|g|\n+-----+\n");
+        assert!(matches!(result, Err(MazeError::NoReachableGoal)));
+    }
+
+    #[test]
+    fn missing_file_is_an_io_error() {
+        assert!(matches!(load_maze("does/not/exist"), Err(MazeError::Io(_))));
+    }
+
+    #[test]
+    fn surface_layers_must_match_the_maze() {
+        let with_floor = |floor: &str| level(&format!("+-----+\n|@ r g|\n+-----+\n[floor]\n{}", floor));
+        assert!(with_floor("\n").is_ok());
+        assert!(with_floor(".......\n.=~ ~=.\n.......\n\n").is_ok());
+        assert!(matches!(
+            with_floor(".......\n.......\n"),
+            Err(MazeError::LayerSize { layer: "floor", expected: 3, found: 2 })
+        ));
+        let Err(MazeError::InvalidLayer { layer: "floor", error }) = with_floor(".......\n.....\n.......\n") else {
+            panic!("se esperaba una capa invalida");
+        };
+        assert!(matches!(*error, MazeError::NotRectangular { row: 1, expected: 7, found: 5 }));
+
+        let ceiling = level("+-----+\n|@ r g|\n+-----+\n[ceiling]\n#######\n##o#x##\n#######\n");
+        let Err(MazeError::InvalidLayer { layer: "ceiling", error }) = ceiling else {
+            panic!("se esperaba una capa invalida");
+        };
+        assert!(matches!(*error, MazeError::UnknownGlyph { row: 1, col: 4, glyph: 'x' }));
+    }
+
+    #[test]
+    fn time_limit_must_be_positive() {
+        let with_time = |time: &str| {
+            parse_level(&format!("[level]\ntime = {}\n[maze]\n+-----+\n|@ k g|\n+-----+\n", time), "prueba")
+        };
+        assert_eq!(with_time("45").unwrap().time_limit, 45.0);
+        for time in ["0", "-10", "inf", "abc"] {
+            assert!(matches!(with_time(time), Err(MazeError::InvalidMetadata { .. })), "{}", time);
+        }
+    }
+
+    #[test]
+    fn header_sets_the_player_radius() {
+        let with_radius = |radius: &str| {
+            parse_level(&format!("[level]\nradius = {}\n[maze]\n+-----+\n|@ k g|\n+-----+\n", radius), "prueba")
+        };
+        assert_eq!(with_radius("30").unwrap().movement.radius, 30.0);
+        assert!(matches!(with_radius("0"), Err(MazeError::InvalidMetadata { .. })));
+        assert!(matches!(with_radius("80"), Err(MazeError::InvalidMetadata { .. })));
+    }
+}
@@ -0,0 +1,264 @@
+// navigation.rs
+// Búsqueda de caminos y análisis de alcance sobre la cuadrícula del laberinto.
+// Las celdas se expresan como (columna, fila) y solo se camina en las cuatro
+// direcciones cardinales, igual que el jugador atraviesa las celdas.
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, VecDeque};
+use crate::maze::{is_walkable, EntityKind, Level, Maze};
+use crate::key::KeyColor;
+
+pub type Cell = (usize, usize);
+
+fn cell_at(maze: &Maze, (x, y): Cell) -> Option<char> {
+    maze.get(y).and_then(|row| row.get(x)).copied()
+}
+
+// Vecinos transitables de una celda (no asume que el borde esté cerrado)
+pub fn neighbors(maze: &Maze, cell: Cell) -> impl Iterator<Item = Cell> + '_ {
+    neighbors_where(maze, cell, is_walkable)
+}
+
+// Vecinos cuyo glifo cumple `passable`
+fn neighbors_where<'a>(maze: &'a Maze, (x, y): Cell, passable: impl Fn(char) -> bool + 'a) -> impl Iterator<Item = Cell> + 'a {
+    let candidates = [
+        (x.checked_sub(1), Some(y)),
+        (Some(x + 1), Some(y)),
+        (Some(x), y.checked_sub(1)),
+        (Some(x), Some(y + 1)),
+    ];
+    candidates
+        .into_iter()
+        .filter_map(|(x, y)| Some((x?, y?)))
+        .filter(move |&cell| cell_at(maze, cell).is_some_and(&passable))
+}
+
+// Distancia en pasos desde las celdas origen hasta cada celda alcanzable
+pub struct DistanceField {
+    width: usize,
+    distances: Vec<Option<u32>>,
+}
+
+impl DistanceField {
+    pub fn get(&self, (x, y): Cell) -> Option<u32> {
+        if x >= self.width {
+            return None;
+        }
+        self.distances.get(y * self.width + x).copied().flatten()
+    }
+
+    pub fn reachable(&self, cell: Cell) -> bool {
+        self.get(cell).is_some()
+    }
+
+    // Celdas alcanzables, con su distancia
+    pub fn cells(&self) -> impl Iterator<Item = (Cell, u32)> + '_ {
+        self.distances
+            .iter()
+            .enumerate()
+            .filter_map(|(i, d)| d.map(|d| ((i % self.width, i / self.width), d)))
+    }
+}
+
+// BFS desde varios orígenes a la vez (relleno por inundación con distancias)
+pub fn distance_field(maze: &Maze, sources: &[Cell]) -> DistanceField {
+    distance_field_where(maze, sources, is_walkable)
+}
+
+// Igual que `distance_field`, pero solo cruza las celdas cuyo glifo cumple `passable`
+fn distance_field_where(maze: &Maze, sources: &[Cell], passable: impl Fn(char) -> bool + Copy) -> DistanceField {
+    let width = maze.iter().map(|row| row.len()).max().unwrap_or(0);
+    let mut field = DistanceField {
+        width,
+        distances: vec![None; width * maze.len()],
+    };
+    let mut queue = VecDeque::new();
+    for &(x, y) in sources {
+        if cell_at(maze, (x, y)).is_some_and(passable) && field.get((x, y)).is_none() {
+            field.distances[y * width + x] = Some(0);
+            queue.push_back((x, y));
+        }
+    }
+    while let Some(cell) = queue.pop_front() {
+        let d = field.get(cell).unwrap_or(0);
+        for (nx, ny) in neighbors_where(maze, cell, passable) {
+            let slot = &mut field.distances[ny * width + nx];
+            if slot.is_none() {
+                *slot = Some(d + 1);
+                queue.push_back((nx, ny));
+            }
+        }
+    }
+    field
+}
+
+fn rebuild_path(came_from: &HashMap<Cell, Cell>, start: Cell, goal: Cell) -> Vec<Cell> {
+    let mut path = vec![goal];
+    let mut cell = goal;
+    while cell != start {
+        cell = came_from[&cell];
+        path.push(cell);
+    }
+    path.reverse();
+    path
+}
+
+fn manhattan(a: Cell, b: Cell) -> u32 {
+    (a.0.abs_diff(b.0) + a.1.abs_diff(b.1)) as u32
+}
+
+// Camino más corto por A* con heurística Manhattan (admisible en 4 direcciones)
+pub fn astar_path(maze: &Maze, start: Cell, goal: Cell) -> Option<Vec<Cell>> {
+    if !cell_at(maze, start).is_some_and(is_walkable) {
+        return None;
+    }
+    let mut came_from = HashMap::new();
+    let mut cost: HashMap<Cell, u32> = HashMap::from([(start, 0)]);
+    let mut open = BinaryHeap::from([Reverse((manhattan(start, goal), start))]);
+    while let Some(Reverse((_, cell))) = open.pop() {
+        if cell == goal {
+            return Some(rebuild_path(&came_from, start, goal));
+        }
+        let next_cost = cost[&cell] + 1;
+        for next in neighbors(maze, cell) {
+            if cost.get(&next).is_none_or(|&c| next_cost < c) {
+                cost.insert(next, next_cost);
+                came_from.insert(next, cell);
+                open.push(Reverse((next_cost + manhattan(next, goal), next)));
+            }
+        }
+    }
+    None
+}
+
+// Alcance desde la aparición cuando las puertas con cerradura solo se cruzan
+// con su llave: se recogen las llaves al alcance y se repite hasta que no
+// aparezca ninguna nueva. Devuelve el alcance final y los colores conseguidos.
+pub fn reachable_with_keys(level: &Level) -> (DistanceField, Vec<KeyColor>) {
+    let mut held: Vec<KeyColor> = Vec::new();
+    loop {
+        let field = distance_field_where(&level.maze, &[level.spawn], |cell| match KeyColor::from_gate_glyph(cell) {
+            Some(color) => held.contains(&color),
+            None => is_walkable(cell),
+        });
+        let before = held.len();
+        for (color, cell) in level.keys() {
+            if field.reachable(cell) && !held.contains(&color) {
+                held.push(color);
+            }
+        }
+        if held.len() == before {
+            return (field, held);
+        }
+    }
+}
+
+// Pasos mínimos desde `from` recogiendo una llave de cada color pendiente, en
+// cualquier orden, y terminando en una meta. `fields` tiene el campo de
+// distancias de la aparición y de cada llave, calculados una sola vez.
+fn tour_steps(
+    level: &Level,
+    from: Cell,
+    pending: &[KeyColor],
+    fields: &HashMap<Cell, DistanceField>,
+    to_goal: &DistanceField,
+) -> Option<u32> {
+    if pending.is_empty() {
+        return to_goal.get(from);
+    }
+    let field = &fields[&from];
+    let mut best = None;
+    for (i, &color) in pending.iter().enumerate() {
+        let mut rest = pending.to_vec();
+        rest.remove(i);
+        for key in level.cells_of(EntityKind::Key(color)) {
+            let Some(to_key) = field.get(key) else {
+                continue;
+            };
+            if let Some(steps) = tour_steps(level, key, &rest, fields, to_goal) {
+                let total = to_key + steps;
+                best = Some(best.map_or(total, |b: u32| b.min(total)));
+            }
+        }
+    }
+    best
+}
+
+// Pasos mínimos para completar el nivel: aparición -> las llaves que pide la
+// salida -> una meta. Las puertas se consideran abiertas.
+pub fn par_steps(level: &Level) -> Option<u32> {
+    let goals: Vec<Cell> = level
+        .maze
+        .iter()
+        .enumerate()
+        .flat_map(|(y, row)| row.iter().enumerate().filter(|&(_, &c)| c == 'g').map(move |(x, _)| (x, y)))
+        .collect();
+    let to_goal = distance_field(&level.maze, &goals);
+    let starts = std::iter::once(level.spawn).chain(
+        level.keys().filter(|(color, _)| level.exit_keys.contains(color)).map(|(_, cell)| cell),
+    );
+    let fields: HashMap<Cell, DistanceField> = starts.map(|cell| (cell, distance_field(&level.maze, &[cell]))).collect();
+    tour_steps(level, level.spawn, &level.exit_keys, &fields, &to_goal)
+}
+
+// Tiempo de referencia del nivel, en segundos, a `cells_per_second`
+pub fn par_time(level: &Level, cells_per_second: f32) -> Option<f32> {
+    par_steps(level).map(|steps| steps as f32 / cells_per_second)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::maze::parse_level;
+
+    // Aparición en (1,1), llave al final del pasillo de arriba y meta abajo a la izquierda
+    const GRID: [&str; 5] = [
+        "+------+",
+        "|@    k|",
+        "+--+ +-+",
+        "|g     |",
+        "+------+",
+    ];
+
+    fn maze() -> Maze {
+        GRID.iter().map(|row| row.chars().map(|c| if c == '@' || c == 'k' { ' ' } else { c }).collect()).collect()
+    }
+
+    fn level(exit: &str) -> Level {
+        parse_level(&format!("[level]\nexit = {}\n[maze]\n{}\n", exit, GRID.join("\n")), "prueba").unwrap()
+    }
+
+    #[test]
+    fn distance_field_counts_steps() {
+        let field = distance_field(&maze(), &[(1, 1)]);
+        assert_eq!(field.get((1, 1)), Some(0));
+        assert_eq!(field.get((4, 1)), Some(3));
+        assert_eq!(field.get((4, 3)), Some(5));
+        assert_eq!(field.get((1, 3)), Some(8));
+        assert_eq!(field.get((0, 0)), None);
+    }
+
+    #[test]
+    fn astar_matches_bfs_length() {
+        let maze = maze();
+        let field = distance_field(&maze, &[(1, 1)]);
+        for (cell, steps) in field.cells() {
+            let path = astar_path(&maze, (1, 1), cell).expect("celda alcanzable");
+            assert_eq!(path.len() as u32 - 1, steps, "camino a {:?}", cell);
+            assert_eq!((path[0], path[path.len() - 1]), ((1, 1), cell));
+        }
+    }
+
+    #[test]
+    fn astar_returns_none_for_unreachable_goal() {
+        let mut maze = maze();
+        maze[2][4] = '+'; // Cierra el único paso entre los dos pasillos
+        assert_eq!(astar_path(&maze, (1, 1), (1, 3)), None);
+        assert_eq!(astar_path(&maze, (0, 0), (1, 1)), None);
+    }
+
+    #[test]
+    fn par_steps_goes_through_the_required_keys() {
+        assert_eq!(par_steps(&level("none")), Some(8));
+        assert_eq!(par_steps(&level("gold")), Some(12));
+    }
+}
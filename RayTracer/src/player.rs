@@ -1,38 +1,173 @@
 // src/player.rs
 use raylib::prelude::*;
 use std::f32::consts::PI;
-use crate::maze::Maze;
+use crate::maze::{is_door, Maze};
+use crate::door::Doors;
+use crate::input::{Action, InputMap};
+
+// Velocidad al caminar en unidades de mundo por segundo (8 por cuadro a 60 FPS)
+pub const MOVE_SPEED: f32 = 480.0;
+// Giro con teclado en radianes por segundo
+const ROTATION_SPEED: f32 = PI * 1.5;
+// Radio del jugador (unidades de mundo): distancia mínima a las paredes
+pub const DEFAULT_PLAYER_RADIUS: f32 = 20.0;
+// Radio máximo que acepta un nivel: por debajo de media celda para caber por un pasillo
+pub const MAX_PLAYER_RADIUS: f32 = 45.0;
+// Después de agotarse, la resistencia debe recuperar esta fracción para volver a correr
+const STAMINA_RECOVERY: f32 = 0.3;
+
+// Parámetros del movimiento. Cada nivel los puede ajustar en su cabecera.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Movement {
+    pub speed: f32,         // Velocidad máxima caminando (unidades/s)
+    pub sprint_factor: f32, // Multiplicador de la velocidad al correr
+    pub acceleration: f32,  // Cuánto gana velocidad con las teclas presionadas (unidades/s²)
+    pub friction: f32,      // Cuánto la pierde al soltarlas (unidades/s²)
+    pub stamina_drain: f32, // Resistencia gastada por segundo corriendo (1 = completa)
+    pub stamina_regen: f32, // Resistencia recuperada por segundo sin correr
+    pub radius: f32,        // Distancia mínima a las paredes
+}
+
+impl Default for Movement {
+    fn default() -> Self {
+        Movement {
+            speed: MOVE_SPEED,
+            sprint_factor: 1.6,
+            acceleration: 3000.0,
+            friction: 4000.0,
+            stamina_drain: 0.35,
+            stamina_regen: 0.2,
+            radius: DEFAULT_PLAYER_RADIUS,
+        }
+    }
+}
 
 pub struct Player {
     pub pos: Vector2,
     pub a: f32,
     pub fov: f32,
+    pub velocity: Vector2,
+    pub stamina: f32,     // 0..1
+    pub exhausted: bool,  // Se agotó corriendo y aún no se recupera
+    pub movement: Movement,
+    pub mouse_sensitivity: f32, // Radianes por píxel (ver `Settings`)
+}
+
+impl Player {
+    pub fn new(pos: Vector2, a: f32) -> Self {
+        Player {
+            pos,
+            a,
+            fov: PI / 3.0,
+            velocity: Vector2::new(0.0, 0.0),
+            stamina: 1.0,
+            exhausted: false,
+            movement: Movement::default(),
+            mouse_sensitivity: 0.002,
+        }
+    }
+
+    // Coloca al jugador al inicio de un nivel, quieto y descansado
+    pub fn respawn(&mut self, pos: Vector2, a: f32, movement: Movement) {
+        self.pos = pos;
+        self.a = a;
+        self.velocity = Vector2::new(0.0, 0.0);
+        self.stamina = 1.0;
+        self.exhausted = false;
+        self.movement = movement;
+    }
+}
+
+// Acerca `current` a `target` a lo sumo `max_delta` (en longitud)
+fn approach(current: Vector2, target: Vector2, max_delta: f32) -> Vector2 {
+    let dx = target.x - current.x;
+    let dy = target.y - current.y;
+    let distance = (dx * dx + dy * dy).sqrt();
+    if distance <= max_delta || distance == 0.0 {
+        return target;
+    }
+    let f = max_delta / distance;
+    Vector2::new(current.x + dx * f, current.y + dy * f)
+}
+
+// Celdas que frenan al jugador: paredes, puertas cerradas y fuera del mapa.
+// La meta se puede pisar (cuando se tiene la llave se verifica en main).
+fn is_solid(maze: &Maze, doors: &Doors, x: isize, y: isize) -> bool {
+    if x < 0 || y < 0 {
+        return true;
+    }
+    let (x, y) = (x as usize, y as usize);
+    match maze.get(y).and_then(|row| row.get(x)) {
+        Some(' ') | Some('g') => false,
+        Some(&cell) if is_door(cell) => !doors.is_passable((x, y)),
+        _ => true,
+    }
+}
+
+// ¿El círculo de radio `radius` en `pos` toca alguna celda sólida?
+fn collides(maze: &Maze, doors: &Doors, pos: Vector2, radius: f32, block_size: usize) -> bool {
+    let block = block_size as f32;
+    let min_x = ((pos.x - radius) / block).floor() as isize;
+    let max_x = ((pos.x + radius) / block).floor() as isize;
+    let min_y = ((pos.y - radius) / block).floor() as isize;
+    let max_y = ((pos.y + radius) / block).floor() as isize;
+
+    for y in min_y..=max_y {
+        for x in min_x..=max_x {
+            if !is_solid(maze, doors, x, y) {
+                continue;
+            }
+            // Punto de la celda más cercano al centro del círculo
+            let closest_x = pos.x.clamp(x as f32 * block, (x + 1) as f32 * block);
+            let closest_y = pos.y.clamp(y as f32 * block, (y + 1) as f32 * block);
+            if (pos.x - closest_x).powi(2) + (pos.y - closest_y).powi(2) < radius * radius {
+                return true;
+            }
+        }
+    }
+    false
+}
+
+// Mueve al jugador por `delta` resolviendo cada eje por separado, así al
+// chocar en diagonal se desliza a lo largo de la pared en vez de detenerse.
+// Los movimientos largos (un `dt` grande) se parten en pasos menores que el
+// radio para no atravesar paredes. Devuelve si la posición cambió.
+fn move_and_slide(player: &mut Player, delta: Vector2, maze: &Maze, doors: &Doors, block_size: usize) -> bool {
+    let start = player.pos;
+    let length = (delta.x * delta.x + delta.y * delta.y).sqrt();
+    let radius = player.movement.radius;
+    let steps = (length / radius.max(1.0)).ceil().max(1.0);
+    let step = Vector2::new(delta.x / steps, delta.y / steps);
+
+    for _ in 0..steps as usize {
+        let along_x = Vector2::new(player.pos.x + step.x, player.pos.y);
+        if !collides(maze, doors, along_x, radius, block_size) {
+            player.pos = along_x;
+        }
+        let along_y = Vector2::new(player.pos.x, player.pos.y + step.y);
+        if !collides(maze, doors, along_y, radius, block_size) {
+            player.pos = along_y;
+        }
+    }
+    player.pos != start
 }
 
 // Modificamos la función para que maneje solo movimiento y rotación, y devuelva si se movió o no.
 pub fn process_events(
     window: &RaylibHandle, // Cambiado a &RaylibHandle para no necesitar &mut
+    input: &InputMap,
     player: &mut Player,
     maze: &Maze,
+    doors: &Doors,
     block_size: usize,
-) -> bool { // Devuelve true si el jugador se movió físicamente
-    const MOVE_SPEED: f32 = 8.0;
-    const ROTATION_SPEED: f32 = PI / 40.0; // Ajustado según ejemplo
-    const MOUSE_SENSITIVITY: f32 = 0.002; // Ajustado según ejemplo
-
-    // Obtener el delta del mouse directamente aquí
-    let mouse_delta = window.get_mouse_delta();
+    dt: f32,
+) -> bool { // Devuelve true si el jugador caminó: se movió con entrada de movimiento
+    // Rotación con el eje de mirada (ratón)
+    player.a += input.axis(window, Action::Look) * player.mouse_sensitivity;
 
-    // Rotación con mouse (solo ángulo)
-    player.a += mouse_delta.x * MOUSE_SENSITIVITY;
-
-    // Rotación con teclado (opcional, para redundancia o controles alternativos)
-    if window.is_key_down(KeyboardKey::KEY_LEFT) || window.is_key_down(KeyboardKey::KEY_A) {
-        player.a -= ROTATION_SPEED;
-    }
-    if window.is_key_down(KeyboardKey::KEY_RIGHT) || window.is_key_down(KeyboardKey::KEY_D) {
-        player.a += ROTATION_SPEED;
-    }
+    // Rotación con teclas o la palanca derecha (proporcional a la inclinación)
+    let turn = input.value(window, Action::TurnRight) - input.value(window, Action::TurnLeft);
+    player.a += turn * ROTATION_SPEED * dt;
 
     // Normalizar el ángulo para evitar overflow
     if player.a > PI {
@@ -41,38 +176,99 @@ pub fn process_events(
         player.a += 2.0 * PI;
     }
 
-    let mut next_pos = player.pos;
-    let mut moved = false; // Indica si se intentó mover (antes de colisión)
+    // Dirección deseada: adelante/atrás e izquierda/derecha (0..1 con la palanca)
+    let forward = input.value(window, Action::MoveForward) - input.value(window, Action::MoveBackward);
+    let strafe = input.value(window, Action::StrafeRight) - input.value(window, Action::StrafeLeft);
+    let (sin, cos) = player.a.sin_cos();
+    // La derecha de la vista es el ángulo + 90° (y crece hacia abajo)
+    let mut wish = Vector2::new(forward * cos - strafe * sin, forward * sin + strafe * cos);
+    let wish_length = (wish.x * wish.x + wish.y * wish.y).sqrt();
+    let walking = wish_length > 0.0;
+    // Se limita a 1 en vez de normalizar: la palanca a medias camina más lento
+    if wish_length > 1.0 {
+        wish = Vector2::new(wish.x / wish_length, wish.y / wish_length);
+    }
 
-    // Movimiento con teclado (WASD o Flechas)
-    if window.is_key_down(KeyboardKey::KEY_UP) || window.is_key_down(KeyboardKey::KEY_W) {
-        next_pos.x += MOVE_SPEED * player.a.cos();
-        next_pos.y += MOVE_SPEED * player.a.sin();
-        moved = true;
+    // Correr gasta resistencia; agotada, hay que esperar a que se recupere
+    let movement = player.movement;
+    let sprinting = walking && !player.exhausted && input.down(window, Action::Sprint);
+    if sprinting {
+        player.stamina = (player.stamina - movement.stamina_drain * dt).max(0.0);
+        player.exhausted = player.stamina == 0.0;
+    } else {
+        player.stamina = (player.stamina + movement.stamina_regen * dt).min(1.0);
+        if player.stamina >= STAMINA_RECOVERY {
+            player.exhausted = false;
+        }
     }
-    if window.is_key_down(KeyboardKey::KEY_DOWN) || window.is_key_down(KeyboardKey::KEY_S) {
-        next_pos.x -= MOVE_SPEED * player.a.cos();
-        next_pos.y -= MOVE_SPEED * player.a.sin();
-        moved = true;
+
+    // Aceleración hacia la velocidad deseada, o fricción hasta detenerse
+    let speed = movement.speed * if sprinting { movement.sprint_factor } else { 1.0 };
+    player.velocity = if walking {
+        approach(player.velocity, Vector2::new(wish.x * speed, wish.y * speed), movement.acceleration * dt)
+    } else {
+        approach(player.velocity, Vector2::new(0.0, 0.0), movement.friction * dt)
+    };
+    if dt <= 0.0 || (player.velocity.x == 0.0 && player.velocity.y == 0.0) {
+        return false;
     }
 
-    // Verificar colisiones y aplicar movimiento
-    if moved {
-        let grid_x = next_pos.x as usize / block_size;
-        let grid_y = next_pos.y as usize / block_size;
+    let start = player.pos;
+    let delta = Vector2::new(player.velocity.x * dt, player.velocity.y * dt);
+    let moved = move_and_slide(player, delta, maze, doors, block_size);
+    // Lo que la pared frenó se pierde (así se desliza sin acumular empuje)
+    player.velocity = Vector2::new((player.pos.x - start.x) / dt, (player.pos.y - start.y) / dt);
+    // Deslizarse por la inercia tras soltar las teclas no cuenta como pasos
+    moved && walking
+}
 
-        // Verificar límites del laberinto
-        if grid_y < maze.len() && grid_x < maze[grid_y].len() {
-            // Permitir movimiento si es espacio vacío o la meta (cuando se tiene la llave se verifica en main)
-            if maze[grid_y][grid_x] == ' ' || maze[grid_y][grid_x] == 'g' {
-                player.pos = next_pos;
-                return true; // El jugador se movió físicamente
-            }
-        }
-        // Si llegó aquí, hubo intento de movimiento pero colisión, devolver false
-        return false;
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BLOCK: usize = 100;
+
+    fn grid(rows: &[&str]) -> Maze {
+        rows.iter().map(|row| row.chars().collect()).collect()
     }
 
-    // Si no se intentó mover, devolver false
-    false
-}
\ No newline at end of file
+    fn slide(maze: &Maze, pos: (f32, f32), delta: (f32, f32), radius: f32) -> (Player, bool) {
+        let mut player = Player::new(Vector2::new(pos.0, pos.1), 0.0);
+        player.movement.radius = radius;
+        let moved = move_and_slide(&mut player, Vector2::new(delta.0, delta.1), maze, &Doors::from_maze(maze), BLOCK);
+        (player, moved)
+    }
+
+    #[test]
+    fn diagonal_into_a_wall_slides_along_it() {
+        let maze = grid(&["#####", "#   #", "#   #", "#####"]);
+        // Pegado a la pared de arriba: el eje y se bloquea y el x sigue
+        let (player, moved) = slide(&maze, (150.0, 125.0), (50.0, -50.0), DEFAULT_PLAYER_RADIUS);
+        assert!(moved);
+        assert_eq!((player.pos.x, player.pos.y), (200.0, 125.0));
+    }
+
+    #[test]
+    fn moving_past_a_corner_does_not_cut_it() {
+        let maze = grid(&["#####", "#   #", "# # #", "#   #", "#####"]);
+        let doors = Doors::from_maze(&maze);
+        // En diagonal hacia la esquina de la celda sólida (2, 2)
+        let (player, moved) = slide(&maze, (170.0, 170.0), (60.0, 60.0), DEFAULT_PLAYER_RADIUS);
+        assert!(moved);
+        assert!(!collides(&maze, &doors, player.pos, DEFAULT_PLAYER_RADIUS, BLOCK));
+        // El círculo queda fuera de la esquina aunque su caja la toque
+        let corner = Vector2::new(200.0, 200.0);
+        assert!((player.pos.x - corner.x).hypot(player.pos.y - corner.y) >= DEFAULT_PLAYER_RADIUS);
+    }
+
+    #[test]
+    fn long_moves_stop_at_the_radius() {
+        let maze = grid(&["#######", "#  #  #", "#######"]);
+        for radius in [DEFAULT_PLAYER_RADIUS, 35.0] {
+            // Un `dt` enorme no atraviesa la pared de la columna 3
+            let (player, _) = slide(&maze, (150.0, 150.0), (400.0, 0.0), radius);
+            assert!(player.pos.x <= 300.0 - radius);
+            assert!(player.pos.x > 300.0 - 2.0 * radius);
+        }
+    }
+}
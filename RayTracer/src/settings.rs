@@ -0,0 +1,301 @@
+// settings.rs
+// Preferencias del jugador (sensibilidad, campo de visión, volumen, ventana y
+// pasos). Se guardan en `settings.toml` dentro de la carpeta de configuración
+// del usuario y se editan desde la pantalla de opciones del menú.
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+use crate::framebuffer::ScaleMode;
+
+// Carpeta propia dentro de la configuración del usuario
+const APP_DIR: &str = "raycaster";
+const SETTINGS_FILE: &str = "settings.toml";
+
+// Límites del tamaño de ventana (también para `--window`)
+const MIN_WINDOW_SIZE: (i32, i32) = (640, 480);
+const MAX_WINDOW_SIZE: (i32, i32) = (3840, 2160);
+
+// Tamaños de ventana que ofrece la pantalla de opciones
+const WINDOW_SIZES: [(i32, i32); 5] = [(960, 640), (1300, 900), (1600, 1000), (1920, 1080), (2560, 1440)];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Settings {
+    pub mouse_sensitivity: f32, // Radianes por píxel de movimiento del ratón
+    pub fov_degrees: f32,
+    pub music_volume: f32,      // 0..1
+    pub window_width: i32,
+    pub window_height: i32,
+    pub step_cooldown_ms: u64,  // Pausa mínima entre sonidos de pasos
+    pub smooth_scaling: bool,   // Escalado libre y bilineal en vez de por factor entero
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            mouse_sensitivity: 0.002,
+            fov_degrees: 60.0,
+            music_volume: 0.3,
+            window_width: 1300,
+            window_height: 900,
+            step_cooldown_ms: 250,
+            smooth_scaling: false,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum SettingsError {
+    Io(io::Error),
+    InvalidLine { line: usize },
+}
+
+impl fmt::Display for SettingsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SettingsError::Io(e) => write!(f, "{}", e),
+            SettingsError::InvalidLine { line } => write!(f, "línea {}: se esperaba `opcion = valor`", line),
+        }
+    }
+}
+
+impl std::error::Error for SettingsError {}
+
+impl From<io::Error> for SettingsError {
+    fn from(e: io::Error) -> Self {
+        SettingsError::Io(e)
+    }
+}
+
+// Carpeta de configuración del usuario según el sistema
+// (XDG en Linux, Application Support en macOS, %APPDATA% en Windows)
+fn config_dir() -> Option<PathBuf> {
+    let env = |name: &str| std::env::var_os(name).filter(|value| !value.is_empty()).map(PathBuf::from);
+    if cfg!(target_os = "windows") {
+        env("APPDATA")
+    } else if cfg!(target_os = "macos") {
+        env("HOME").map(|home| home.join("Library/Application Support"))
+    } else {
+        env("XDG_CONFIG_HOME").or_else(|| env("HOME").map(|home| home.join(".config")))
+    }
+}
+
+// Ruta del archivo de opciones, si se puede determinar la carpeta del usuario
+pub fn settings_path() -> Option<PathBuf> {
+    config_dir().map(|dir| dir.join(APP_DIR).join(SETTINGS_FILE))
+}
+
+impl Settings {
+    pub fn load(path: &std::path::Path) -> Result<Self, SettingsError> {
+        Settings::parse(&std::fs::read_to_string(path)?)
+    }
+
+    // Subconjunto de TOML: líneas `opcion = valor`, `#` inicia un comentario.
+    // Las opciones que faltan conservan su valor de fábrica.
+    pub fn parse(text: &str) -> Result<Self, SettingsError> {
+        let mut settings = Settings::default();
+        for (index, line) in text.lines().enumerate() {
+            let trimmed = line.split('#').next().unwrap_or("").trim();
+            if trimmed.is_empty() {
+                continue;
+            }
+            let invalid = || SettingsError::InvalidLine { line: index + 1 };
+            let (name, value) = trimmed.split_once('=').ok_or_else(invalid)?;
+            let number: f32 = value.trim().parse().map_err(|_| invalid())?;
+            if !number.is_finite() || number < 0.0 {
+                return Err(invalid());
+            }
+            match name.trim() {
+                "mouse_sensitivity" => settings.mouse_sensitivity = number,
+                "fov" => settings.fov_degrees = number,
+                "music_volume" => settings.music_volume = number.min(1.0),
+                "window_width" => settings.window_width = number as i32,
+                "window_height" => settings.window_height = number as i32,
+                "step_cooldown_ms" => settings.step_cooldown_ms = number as u64,
+                "smooth_scaling" => settings.smooth_scaling = number != 0.0,
+                _ => return Err(invalid()),
+            }
+        }
+        settings.clamp();
+        Ok(settings)
+    }
+
+    pub fn save(&self, path: &std::path::Path) -> io::Result<()> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let text = format!(
+            "# Opciones del juego (se reescribe al salir de la pantalla de opciones)\n\
+             mouse_sensitivity = {}\n\
+             fov = {}\n\
+             music_volume = {}\n\
+             window_width = {}\n\
+             window_height = {}\n\
+             step_cooldown_ms = {}\n\
+             smooth_scaling = {}\n",
+            self.mouse_sensitivity, self.fov_degrees, self.music_volume,
+            self.window_width, self.window_height, self.step_cooldown_ms,
+            self.smooth_scaling as u8,
+        );
+        std::fs::write(path, text)
+    }
+
+    // Mantiene los valores en rangos jugables
+    fn clamp(&mut self) {
+        self.mouse_sensitivity = self.mouse_sensitivity.clamp(0.0005, 0.01);
+        self.fov_degrees = self.fov_degrees.clamp(45.0, 120.0);
+        self.music_volume = self.music_volume.clamp(0.0, 1.0);
+        (self.window_width, self.window_height) = clamp_window_size(self.window_width, self.window_height);
+        self.step_cooldown_ms = self.step_cooldown_ms.clamp(100, 1000);
+    }
+
+    pub fn fov(&self) -> f32 {
+        self.fov_degrees.to_radians()
+    }
+
+    // Cómo se escala el cuadro cuando la ventana no tiene su mismo tamaño
+    pub fn scale_mode(&self) -> ScaleMode {
+        if self.smooth_scaling { ScaleMode::Linear } else { ScaleMode::Integer }
+    }
+}
+
+pub fn clamp_window_size(width: i32, height: i32) -> (i32, i32) {
+    (
+        width.clamp(MIN_WINDOW_SIZE.0, MAX_WINDOW_SIZE.0),
+        height.clamp(MIN_WINDOW_SIZE.1, MAX_WINDOW_SIZE.1),
+    )
+}
+
+// Filas de la pantalla de opciones
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SettingField {
+    MouseSensitivity,
+    Fov,
+    MusicVolume,
+    WindowSize,
+    StepCooldown,
+    Scaling,
+}
+
+impl SettingField {
+    pub const ALL: [SettingField; 6] = [
+        SettingField::MouseSensitivity,
+        SettingField::Fov,
+        SettingField::MusicVolume,
+        SettingField::WindowSize,
+        SettingField::StepCooldown,
+        SettingField::Scaling,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            SettingField::MouseSensitivity => "SENSIBILIDAD DEL RATON",
+            SettingField::Fov => "CAMPO DE VISION",
+            SettingField::MusicVolume => "VOLUMEN DE LA MUSICA",
+            SettingField::WindowSize => "TAMANO DE LA VENTANA",
+            SettingField::StepCooldown => "PAUSA ENTRE PASOS",
+            SettingField::Scaling => "ESCALADO DE LA IMAGEN",
+        }
+    }
+
+    // Valor actual tal como se muestra (solo caracteres de la fuente)
+    pub fn value_text(self, settings: &Settings) -> String {
+        match self {
+            SettingField::MouseSensitivity => format!("{:.1}", settings.mouse_sensitivity * 1000.0),
+            SettingField::Fov => format!("{:.0}", settings.fov_degrees),
+            SettingField::MusicVolume => format!("{:.0}%", settings.music_volume * 100.0),
+            SettingField::WindowSize => format!("{}X{}", settings.window_width, settings.window_height),
+            SettingField::StepCooldown => format!("{} MS", settings.step_cooldown_ms),
+            SettingField::Scaling => String::from(if settings.smooth_scaling { "SUAVE" } else { "NITIDO" }),
+        }
+    }
+
+    // Sube (`direction` > 0) o baja el valor un paso
+    pub fn adjust(self, settings: &mut Settings, direction: i32) {
+        let d = direction.signum() as f32;
+        match self {
+            SettingField::MouseSensitivity => settings.mouse_sensitivity += 0.0005 * d,
+            SettingField::Fov => settings.fov_degrees += 5.0 * d,
+            // Redondeo a décimas para no acumular errores de coma flotante
+            SettingField::MusicVolume => settings.music_volume = ((settings.music_volume + 0.1 * d) * 10.0).round() / 10.0,
+            SettingField::WindowSize => {
+                let area = settings.window_width * settings.window_height;
+                let next = if direction > 0 {
+                    WINDOW_SIZES.iter().find(|(w, h)| w * h > area)
+                } else {
+                    WINDOW_SIZES.iter().rev().find(|(w, h)| w * h < area)
+                };
+                if let Some(&(w, h)) = next {
+                    settings.window_width = w;
+                    settings.window_height = h;
+                }
+            }
+            SettingField::StepCooldown => {
+                settings.step_cooldown_ms = (settings.step_cooldown_ms as i64 + 50 * direction.signum() as i64).max(0) as u64;
+            }
+            SettingField::Scaling => settings.smooth_scaling = !settings.smooth_scaling,
+        }
+        settings.clamp();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_keeps_defaults_for_missing_options() {
+        let settings = Settings::parse("# solo el campo de vision\nfov = 90\n\n").unwrap();
+        assert_eq!(settings, Settings { fov_degrees: 90.0, ..Settings::default() });
+    }
+
+    #[test]
+    fn parse_rejects_unknown_or_malformed_lines() {
+        for text in ["fov 90", "fov = rapido", "brillo = 3", "fov = -5"] {
+            assert!(matches!(Settings::parse(text), Err(SettingsError::InvalidLine { line: 1 })), "{}", text);
+        }
+    }
+
+    #[test]
+    fn parse_clamps_to_playable_ranges() {
+        let settings = Settings::parse("fov = 300\nmusic_volume = 4\nwindow_width = 100\nwindow_height = 9000\n").unwrap();
+        assert_eq!(settings.fov_degrees, 120.0);
+        assert_eq!(settings.music_volume, 1.0);
+        assert_eq!((settings.window_width, settings.window_height), (640, 2160));
+    }
+
+    #[test]
+    fn saved_settings_load_back() {
+        let mut settings = Settings::default();
+        SettingField::Fov.adjust(&mut settings, 1);
+        SettingField::WindowSize.adjust(&mut settings, 1);
+        SettingField::Scaling.adjust(&mut settings, 1);
+        let path = std::env::temp_dir().join(format!("raycaster-settings-{}.toml", std::process::id()));
+        settings.save(&path).unwrap();
+        let loaded = Settings::load(&path);
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(loaded.unwrap(), settings);
+    }
+
+    #[test]
+    fn adjust_steps_and_stops_at_the_limits() {
+        let mut settings = Settings::default();
+        for _ in 0..3 {
+            SettingField::MusicVolume.adjust(&mut settings, -1);
+        }
+        assert_eq!(settings.music_volume, 0.0);
+        SettingField::MusicVolume.adjust(&mut settings, -1);
+        assert_eq!(settings.music_volume, 0.0);
+
+        SettingField::WindowSize.adjust(&mut settings, 1);
+        assert_eq!((settings.window_width, settings.window_height), (1600, 1000));
+        SettingField::WindowSize.adjust(&mut settings, -1);
+        SettingField::WindowSize.adjust(&mut settings, -1);
+        SettingField::WindowSize.adjust(&mut settings, -1);
+        assert_eq!((settings.window_width, settings.window_height), (960, 640));
+
+        for _ in 0..30 {
+            SettingField::StepCooldown.adjust(&mut settings, 1);
+        }
+        assert_eq!(settings.step_cooldown_ms, 1000);
+    }
+}
@@ -0,0 +1,206 @@
+// sprite.rs
+// Renderizado de sprites billboard (llaves, meta, baterías...). Todas las
+// entidades se describen con un `Sprite` y comparten la misma proyección,
+// recorte contra el z-buffer y transparencia.
+use raylib::prelude::*;
+use crate::framebuffer::Framebuffer;
+use crate::player::Player;
+use crate::textures::TextureManager;
+use std::f32::consts::PI;
+
+// Altura de las paredes en unidades de mundo; el ojo está a la mitad
+const WALL_HEIGHT: f32 = 100.0;
+const EYE_HEIGHT: f32 = WALL_HEIGHT / 2.0;
+
+// Planos de recorte (distancia perpendicular en unidades de mundo)
+const NEAR_PLANE: f32 = 50.0;
+const FAR_PLANE: f32 = 1000.0;
+
+// Tamaño por defecto: el mismo que tenían los sprites antes de unificarlos
+pub const DEFAULT_SPRITE_SIZE: f32 = 140.0;
+
+// Punto vertical del sprite que se alinea con el mundo
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Anchor {
+    Center,  // Centrado a la altura de los ojos
+    Floor,   // Apoyado en el piso
+    Ceiling, // Colgando del techo
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Sprite {
+    pub pos: Vector2,
+    pub texture: char, // Textura (o frame de la animación) a dibujar
+    pub size: f32,     // Alto en unidades de mundo; el ancho sale de la proporción de la imagen
+    pub offset: f32,   // Desplazamiento vertical en unidades de mundo (positivo = hacia arriba)
+    pub anchor: Anchor,
+}
+
+impl Sprite {
+    pub fn new(pos: Vector2, texture: char) -> Self {
+        Sprite {
+            pos,
+            texture,
+            size: DEFAULT_SPRITE_SIZE,
+            offset: 0.0,
+            anchor: Anchor::Center,
+        }
+    }
+
+    pub fn with_size(mut self, size: f32) -> Self {
+        self.size = size;
+        self
+    }
+
+    pub fn with_offset(mut self, offset: f32) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    pub fn with_anchor(mut self, anchor: Anchor) -> Self {
+        self.anchor = anchor;
+        self
+    }
+
+    // Altura (en mundo) del borde superior del sprite
+    fn top(&self) -> f32 {
+        match self.anchor {
+            Anchor::Center => EYE_HEIGHT + self.offset + self.size / 2.0,
+            Anchor::Floor => self.offset + self.size,
+            Anchor::Ceiling => WALL_HEIGHT + self.offset,
+        }
+    }
+
+    // Filas de pantalla (arriba, abajo) del sprite con `scale` píxeles por
+    // unidad de mundo; el horizonte `hh` queda a la altura de los ojos
+    fn screen_span(&self, hh: f32, scale: f32) -> (f32, f32) {
+        let top = self.top();
+        let bottom = top - self.size;
+        (hh - (top - EYE_HEIGHT) * scale, hh - (bottom - EYE_HEIGHT) * scale)
+    }
+}
+
+// Ángulo del sprite relativo a la vista, normalizado a [-PI, PI]
+fn relative_angle(player: &Player, pos: Vector2) -> f32 {
+    let sprite_a = (pos.y - player.pos.y).atan2(pos.x - player.pos.x);
+    let mut angle_diff = sprite_a - player.a;
+    while angle_diff > PI {
+        angle_diff -= 2.0 * PI;
+    }
+    while angle_diff < -PI {
+        angle_diff += 2.0 * PI;
+    }
+    angle_diff
+}
+
+// Dibuja un sprite. Las columnas cuya pared (según `depth`) está más cerca
+// que el sprite se omiten; con un `depth` vacío no hay oclusión.
+pub fn draw_sprite(
+    framebuffer: &mut Framebuffer,
+    player: &Player,
+    sprite: &Sprite,
+    texture_manager: &TextureManager,
+    depth: &[f32],
+) {
+    let Some((texture_width, texture_height)) = texture_manager.image_size(sprite.texture) else {
+        return;
+    };
+
+    let angle_diff = relative_angle(player, sprite.pos);
+    let distance = ((player.pos.x - sprite.pos.x).powi(2) + (player.pos.y - sprite.pos.y).powi(2)).sqrt();
+    // Distancia perpendicular, la misma que usan las paredes y el z-buffer
+    let sprite_depth = distance * angle_diff.cos();
+    if !(NEAR_PLANE..=FAR_PLANE).contains(&sprite_depth) {
+        return;
+    }
+
+    let screen_width = framebuffer.width as f32;
+    let screen_height = framebuffer.height as f32;
+    let hh = screen_height / 2.0;
+
+    // Píxeles por unidad de mundo a esta distancia
+    let scale = hh / sprite_depth;
+    let sprite_height = sprite.size * scale;
+    let sprite_width = sprite_height * texture_width as f32 / texture_height as f32;
+    if sprite_width < 1.0 || sprite_height < 1.0 {
+        return;
+    }
+
+    let screen_x = ((angle_diff / player.fov) + 0.5) * screen_width;
+    let left = screen_x - sprite_width / 2.0;
+    let (top, bottom) = sprite.screen_span(hh, scale);
+
+    let start_x = left.max(0.0) as i32;
+    let end_x = (left + sprite_width).min(screen_width) as i32;
+    let start_y = top.max(0.0) as i32;
+    let end_y = bottom.min(screen_height) as i32;
+
+    for x in start_x..end_x {
+        // La pared de esta columna está más cerca: el sprite queda oculto
+        if depth.get(x as usize).is_some_and(|&d| d < sprite_depth) {
+            continue;
+        }
+        let tx = ((x as f32 + 0.5 - left) / sprite_width * texture_width as f32) as u32;
+        for y in start_y..end_y {
+            let ty = ((y as f32 + 0.5 - top) / sprite_height * texture_height as f32) as u32;
+            let color = texture_manager.get_pixel_color(sprite.texture, tx, ty);
+            if color.a != 0 {
+                framebuffer.set_pixel_color(x, y, color);
+            }
+        }
+    }
+}
+
+// Dibuja todos los sprites de lejos a cerca para que los cercanos tapen a los lejanos
+pub fn draw_sprites(
+    framebuffer: &mut Framebuffer,
+    player: &Player,
+    sprites: &mut [Sprite],
+    texture_manager: &TextureManager,
+    depth: &[f32],
+) {
+    let distance = |sprite: &Sprite| {
+        (sprite.pos.x - player.pos.x).powi(2) + (sprite.pos.y - player.pos.y).powi(2)
+    };
+    sprites.sort_by(|a, b| distance(b).total_cmp(&distance(a)));
+
+    for sprite in sprites.iter() {
+        draw_sprite(framebuffer, player, sprite, texture_manager, depth);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Distancia del piso que pinta el render en la fila `row` (misma
+    // fórmula que `render_world`, con las paredes de WALL_HEIGHT de alto)
+    fn floor_distance(hh: f32, row: f32) -> f32 {
+        hh * WALL_HEIGHT * 0.5 / (row - hh)
+    }
+
+    #[test]
+    fn floor_sprite_bottom_lands_on_the_floor_line() {
+        let hh = 450.0;
+        for depth in [80.0, 250.0, 900.0] {
+            let sprite = Sprite::new(Vector2::new(0.0, 0.0), 'k').with_size(40.0).with_anchor(Anchor::Floor);
+            let (top, bottom) = sprite.screen_span(hh, hh / depth);
+            assert!((floor_distance(hh, bottom) - depth).abs() < 1e-2, "distancia {}", depth);
+            assert!((bottom - top - 40.0 * hh / depth).abs() < 1e-3);
+        }
+    }
+
+    #[test]
+    fn offset_lifts_the_sprite() {
+        let hh = 450.0;
+        let scale = hh / 200.0;
+        let resting = Sprite::new(Vector2::new(0.0, 0.0), 'b').with_size(30.0).with_anchor(Anchor::Floor);
+        let lifted = resting.with_offset(10.0);
+        let (_, resting_bottom) = resting.screen_span(hh, scale);
+        let (_, lifted_bottom) = lifted.screen_span(hh, scale);
+        assert!((resting_bottom - lifted_bottom - 10.0 * scale).abs() < 1e-3);
+        // Centrado, la mitad queda a cada lado del horizonte
+        let (top, bottom) = Sprite::new(Vector2::new(0.0, 0.0), 'g').screen_span(hh, scale);
+        assert!((hh - top - (bottom - hh)).abs() < 1e-3);
+    }
+}
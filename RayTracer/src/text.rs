@@ -345,6 +345,78 @@ impl Font {
             [0, 0, 0, 0, 1],
         ];
         
+        // Símbolo ':'
+        characters[':' as usize] = [
+            [0, 0, 0, 0, 0],
+            [0, 0, 1, 0, 0],
+            [0, 0, 0, 0, 0],
+            [0, 0, 1, 0, 0],
+            [0, 0, 0, 0, 0],
+        ];
+        
+        // Símbolo '.'
+        characters['.' as usize] = [
+            [0, 0, 0, 0, 0],
+            [0, 0, 0, 0, 0],
+            [0, 0, 0, 0, 0],
+            [0, 0, 0, 0, 0],
+            [0, 0, 1, 0, 0],
+        ];
+        
+        // Símbolo '%'
+        characters['%' as usize] = [
+            [1, 1, 0, 0, 1],
+            [1, 1, 0, 1, 0],
+            [0, 0, 1, 0, 0],
+            [0, 1, 0, 1, 1],
+            [1, 0, 0, 1, 1],
+        ];
+        
+        // Símbolo '('
+        characters['(' as usize] = [
+            [0, 0, 0, 1, 0],
+            [0, 0, 1, 0, 0],
+            [0, 0, 1, 0, 0],
+            [0, 0, 1, 0, 0],
+            [0, 0, 0, 1, 0],
+        ];
+        
+        // Símbolo ')'
+        characters[')' as usize] = [
+            [0, 1, 0, 0, 0],
+            [0, 0, 1, 0, 0],
+            [0, 0, 1, 0, 0],
+            [0, 0, 1, 0, 0],
+            [0, 1, 0, 0, 0],
+        ];
+        
+        // Símbolo '-'
+        characters['-' as usize] = [
+            [0, 0, 0, 0, 0],
+            [0, 0, 0, 0, 0],
+            [0, 1, 1, 1, 0],
+            [0, 0, 0, 0, 0],
+            [0, 0, 0, 0, 0],
+        ];
+        
+        // Símbolo '/'
+        characters['/' as usize] = [
+            [0, 0, 0, 0, 1],
+            [0, 0, 0, 1, 0],
+            [0, 0, 1, 0, 0],
+            [0, 1, 0, 0, 0],
+            [1, 0, 0, 0, 0],
+        ];
+        
+        // Símbolo '!'
+        characters['!' as usize] = [
+            [0, 0, 1, 0, 0],
+            [0, 0, 1, 0, 0],
+            [0, 0, 1, 0, 0],
+            [0, 0, 0, 0, 0],
+            [0, 0, 1, 0, 0],
+        ];
+        
         Font { characters }
     }
     
@@ -368,9 +440,7 @@ impl Font {
                                 let py = y + row as i32 * scale + dy;
                                 
                                 // Dibujar el píxel directamente sin cambiar el color global
-                                if px >= 0 && px < framebuffer.width && py >= 0 && py < framebuffer.height {
-                                    framebuffer.color_buffer.draw_pixel(px, py, color);
-                                }
+                                framebuffer.set_pixel_color(px, py, color);
                             }
                         }
                     }
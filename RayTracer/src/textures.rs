@@ -1,34 +1,82 @@
 // src/textures.rs
 use raylib::prelude::*;
 use std::collections::HashMap;
-use std::slice;
+// CPU copy of an image's pixels. Unlike raylib's Image it owns plain data,
+// so the texture manager can be shared with render worker threads.
+pub struct TextureImage {
+    pub width: i32,
+    pub height: i32,
+    pixels: Vec<Color>,
+}
+impl TextureImage {
+    fn from_image(image: &Image) -> Self {
+        let pixels = image
+            .get_image_data()
+            .iter()
+            .map(|c| Color::new(c.r, c.g, c.b, c.a))
+            .collect();
+        TextureImage {
+            width: image.width,
+            height: image.height,
+            pixels,
+        }
+    }
+}
 pub struct TextureManager {
-    images: HashMap<char, Image>,       // Store images for pixel access
-    textures: HashMap<char, Texture2D>, // Store GPU textures for rendering
+    images: HashMap<char, TextureImage>,   // Store images for pixel access
+    textures: HashMap<char, Texture2D>,    // Store GPU textures for rendering
+    surfaces: HashMap<char, TextureImage>, // Floor/ceiling images, keyed by surface glyph
+}
+// Map characters to texture file paths
+const TEXTURE_FILES: [(char, &str); 15] = [
+    ('+', "assets/textures/wall4.png"),
+    ('-', "assets/textures/wall2.png"),
+    ('|', "assets/textures/wall1.png"),
+    ('g', "assets/textures/wall5.png"),
+    ('k', "assets/textures/key.png"),
+    ('r', "assets/textures/key_red.png"),
+    ('u', "assets/textures/key_blue.png"),
+    ('D', "assets/textures/door.png"),
+    ('K', "assets/textures/gate_gold.png"),
+    ('R', "assets/textures/gate_red.png"),
+    ('U', "assets/textures/gate_blue.png"),
+    ('b', "assets/textures/battery1.png"), // Frame 1
+    ('c', "assets/textures/battery2.png"), // Frame 2
+    ('d', "assets/textures/battery3.png"), // Frame 3
+    ('#', "assets/textures/wall3.png"), // default/fallback
+];
+// Floor/ceiling glyphs live in their own namespace (see maze::Surfaces)
+const SURFACE_FILES: [(char, &str); 5] = [
+    ('.', "assets/textures/floor_tiles.png"),
+    ('=', "assets/textures/carpet.png"),
+    ('~', "assets/textures/water.png"),
+    ('#', "assets/textures/ceiling.png"),
+    ('o', "assets/textures/sky.png"), // skylight
+];
+fn load_images(files: &[(char, &str)]) -> HashMap<char, TextureImage> {
+    let mut images = HashMap::new();
+    for &(ch, path) in files {
+        let image = Image::load_image(path).expect(&format!("Failed to load image {}", path));
+        images.insert(ch, TextureImage::from_image(&image));
+    }
+    images
 }
 impl TextureManager {
     pub fn new(rl: &mut RaylibHandle, thread: &RaylibThread) -> Self {
-        let mut images = HashMap::new();
-        let mut textures = HashMap::new();
-        // Map characters to texture file paths
-        let texture_files = vec![
-            ('+', "assets/textures/wall4.png"),
-            ('-', "assets/textures/wall2.png"),
-            ('|', "assets/textures/wall1.png"),
-            ('g', "assets/textures/wall5.png"),
-            ('k', "assets/textures/key.png"),
-            ('b', "assets/textures/battery1.png"), // Frame 1
-            ('c', "assets/textures/battery2.png"), // Frame 2
-            ('d', "assets/textures/battery3.png"), // Frame 3
-            ('#', "assets/textures/wall3.png"), // default/fallback
-        ];
-        for (ch, path) in texture_files {
-            let image = Image::load_image(path).expect(&format!("Failed to load image {}", path));
+        let mut manager = Self::new_headless();
+        for (ch, path) in TEXTURE_FILES {
             let texture = rl.load_texture(thread, path).expect(&format!("Failed to load texture {}", path));
-            images.insert(ch, image);
-            textures.insert(ch, texture);
+            manager.textures.insert(ch, texture);
+        }
+        manager
+    }
+    // CPU-side images only: no window or GPU context required
+    pub fn new_headless() -> Self {
+        TextureManager {
+            images: load_images(&TEXTURE_FILES),
+            textures: HashMap::new(),
+            surfaces: load_images(&SURFACE_FILES),
         }
-        TextureManager { images, textures }
     }
     pub fn get_pixel_color(&self, ch: char, tx: u32, ty: u32) -> Color {
         if let Some(image) = self.images.get(&ch) {
@@ -39,25 +87,29 @@ impl TextureManager {
             Color::WHITE
         }
     }
+    // Samples a floor/ceiling texture at normalized (u, v); None if the glyph has no texture
+    pub fn get_surface_color(&self, ch: char, u: f32, v: f32) -> Option<Color> {
+        let image = self.surfaces.get(&ch)?;
+        let x = ((u * image.width as f32) as i32).clamp(0, image.width - 1);
+        let y = ((v * image.height as f32) as i32).clamp(0, image.height - 1);
+        Some(get_pixel_color(image, x, y))
+    }
+    // Dimensiones reales de la imagen de una textura de pared/sprite
+    pub fn image_size(&self, ch: char) -> Option<(i32, i32)> {
+        self.images.get(&ch).map(|image| (image.width, image.height))
+    }
+
     pub fn get_texture(&self, ch: char) -> Option<&Texture2D> {
         self.textures.get(&ch)
     }
 }
-fn get_pixel_color(image: &Image, x: i32, y: i32) -> Color {
-    let width = image.width as usize;
-    let height = image.height as usize;
-    if x < 0 || y < 0 || x as usize >= width || y as usize >= height {
+fn get_pixel_color(image: &TextureImage, x: i32, y: i32) -> Color {
+    if x < 0 || y < 0 || x >= image.width || y >= image.height {
         return Color::WHITE;
     }
-    let x = x as usize;
-    let y = y as usize;
-    let data_len = width * height * 4;
-    unsafe {
-        let data = slice::from_raw_parts(image.data as *const u8, data_len);
-        let idx = (y * width + x) * 4;
-        if idx + 3 >= data_len {
-            return Color::WHITE;
-        }
-        Color::new(data[idx], data[idx + 1], data[idx + 2], data[idx + 3])
-    }
-}
\ No newline at end of file
+    image
+        .pixels
+        .get((y * image.width + x) as usize)
+        .copied()
+        .unwrap_or(Color::WHITE)
+}